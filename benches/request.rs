@@ -0,0 +1,52 @@
+use std::convert::TryFrom;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+#[cfg(feature = "pinger")]
+use skelphore::ping::MinimalBehaviour;
+use skelphore::{reqwest::Method, Host, HostConfig, TrivialParams, Xri};
+
+fn host() -> Host {
+    let mut profile = std::collections::HashMap::new();
+    profile.insert("Accept-Language".to_owned(), "en".to_owned());
+    profile.insert("X-Api-Version".to_owned(), "2".to_owned());
+    let config = HostConfig {
+        target: "localhost:8080".parse().expect("Address should be valid"),
+        credentials: Some(skelphore::credentials::Credentials {
+            name: "user".into(),
+            key: "pass".to_owned().into(),
+        }),
+        profiles: std::iter::once(("base".to_owned(), profile)).collect(),
+        profile: Some("base".into()),
+        ..Default::default()
+    };
+    #[cfg(feature = "pinger")]
+    let host = Host::<TrivialParams>::new::<MinimalBehaviour>(config);
+    #[cfg(not(feature = "pinger"))]
+    let host = Host::<TrivialParams>::new(config);
+    host.expect("Host should be created")
+}
+
+fn request(c: &mut Criterion) {
+    let host = host();
+    c.bench_function("request", |b| {
+        b.iter(|| host.request(Method::GET, black_box("/items/42"), None, "xri"))
+    });
+    let id = host.intern_path("/items/42");
+    c.bench_function("request_interned", |b| {
+        b.iter(|| host.request(Method::GET, black_box(&id), None, "xri"))
+    });
+    let xri = Xri::try_from("xri").expect("Xri should be valid");
+    c.bench_function("request_xri", |b| {
+        b.iter(|| host.request_xri(Method::GET, black_box("/items/42"), None, &xri))
+    });
+    c.bench_function("request_built", |b| {
+        b.iter(|| {
+            host.request(Method::GET, black_box("/items/42"), None, "xri")
+                .build()
+                .expect("Request should be built")
+        })
+    });
+}
+
+criterion_group!(benches, request);
+criterion_main!(benches);
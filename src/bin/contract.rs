@@ -0,0 +1,60 @@
+//! Checks the host against the contract suite (JSON file with `host` config and `cases`, see
+//! [`ContractSuite`]), printing the report and exiting with 1 if any case failed.
+//! Usage: `skelphore-contract [--json] <suite.json>`
+
+use std::{env, fs, process::ExitCode};
+
+use skelphore::{
+    check_contract, ping::MinimalBehaviour, timeoutsmap::TrivialKey, ContractSuite, Host,
+    TrivialParams,
+};
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => path = Some(arg),
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: skelphore-contract [--json] <suite.json>");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&path, json) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(path: &str, json: bool) -> Result<bool, String> {
+    let text =
+        fs::read(path).map_err(|error| format!("Failed reading suite '{}': {}", path, error))?;
+    let suite: ContractSuite<TrivialKey> = serde_json::from_slice(&text)
+        .map_err(|error| format!("Failed parsing suite '{}': {}", path, error))?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|error| format!("Failed starting runtime: {}", error))?;
+    let report = runtime.block_on(async {
+        let host = Host::<TrivialParams>::new::<MinimalBehaviour>(suite.host)
+            .map_err(|error| format!("Failed creating host: {}", error))?;
+        Ok::<_, String>(check_contract(&host, &suite.cases).await)
+    })?;
+    if json {
+        let text = serde_json::to_string_pretty(&report)
+            .map_err(|error| format!("Failed serializing report: {}", error))?;
+        println!("{}", text);
+    } else {
+        println!("{}", report);
+    }
+    Ok(report.passed())
+}
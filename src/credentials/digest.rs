@@ -0,0 +1,169 @@
+use md5::Md5;
+use reqwest::{header::HeaderMap, Method};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{hex, Error, HeaderMapInsertString};
+use crate::secret::Secret;
+
+/// Credentials for HTTP Digest authentication (RFC 7616), used to answer the server challenges.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DigestConfig {
+    pub user: String,
+    pub password: Secret<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(self, data: &str) -> String {
+        match self {
+            Self::Md5 => hex(&Md5::digest(data)),
+            Self::Sha256 => hex(&Sha256::digest(data)),
+        }
+    }
+}
+
+/// Parameters of the `WWW-Authenticate: Digest ...` challenge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    /// Whether the `auth` quality of protection was offered.
+    pub qop_auth: bool,
+    pub algorithm: DigestAlgorithm,
+}
+
+impl DigestChallenge {
+    /// Gives None for non-digest challenges and the ones not supported
+    /// (like session algorithms or `auth-int` protection only).
+    pub fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let (mut realm, mut nonce, mut opaque, mut qop, mut algorithm) =
+            (None, None, None, None, DigestAlgorithm::Md5);
+        for (name, value) in parse_params(params) {
+            match name.to_ascii_lowercase().as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                "opaque" => opaque = Some(value),
+                "qop" => qop = Some(value),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().as_str() {
+                        "MD5" => DigestAlgorithm::Md5,
+                        "SHA-256" => DigestAlgorithm::Sha256,
+                        _ => return None,
+                    }
+                }
+                _ => (),
+            }
+        }
+        let qop_auth = match qop {
+            Some(qop) => qop.split(',').any(|x| x.trim() == "auth").then_some(true)?,
+            None => false,
+        };
+        Some(Self {
+            realm: realm?,
+            nonce: nonce?,
+            opaque,
+            qop_auth,
+            algorithm,
+        })
+    }
+}
+
+/// Splits `name=value, name="quoted, value"` list into pairs, unquoting the values.
+fn parse_params(mut params: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    loop {
+        params = params.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        let (name, rest) = match params.split_once('=') {
+            Some(split) => split,
+            None => return pairs,
+        };
+        let rest = rest.trim_start();
+        let (value, rest) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                        '"' => {
+                            end = index + 1;
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                (value, &quoted[end..])
+            }
+            None => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                (rest[..end].trim().to_owned(), &rest[end..])
+            }
+        };
+        pairs.push((name.trim().to_owned(), value));
+        params = rest;
+    }
+}
+
+impl DigestConfig {
+    /// Makes `Authorization` header answering the challenge for the request with the given
+    /// method and URI (path and query), using `cnonce` as the client nonce.
+    pub fn authorize(
+        &self,
+        challenge: &DigestChallenge,
+        method: &Method,
+        uri: &str,
+        cnonce: &str,
+    ) -> Result<HeaderMap, Error> {
+        const NONCE_COUNT: &str = "00000001";
+
+        let algorithm = challenge.algorithm;
+        let ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.user,
+            challenge.realm,
+            self.password.expose()
+        ));
+        let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+        let response = match challenge.qop_auth {
+            true => algorithm.hash(&format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, challenge.nonce, NONCE_COUNT, cnonce, ha2
+            )),
+            false => algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, ha2)),
+        };
+
+        let mut value = format!(
+            r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", response="{}", algorithm={}"#,
+            self.user,
+            challenge.realm,
+            challenge.nonce,
+            uri,
+            response,
+            match algorithm {
+                DigestAlgorithm::Md5 => "MD5",
+                DigestAlgorithm::Sha256 => "SHA-256",
+            }
+        );
+        if challenge.qop_auth {
+            value += &format!(r#", qop=auth, nc={}, cnonce="{}""#, NONCE_COUNT, cnonce);
+        }
+        if let Some(opaque) = &challenge.opaque {
+            value += &format!(r#", opaque="{}""#, opaque);
+        }
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert_secret("Authorization", &value.into())?;
+        Ok(headers)
+    }
+}
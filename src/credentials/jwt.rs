@@ -0,0 +1,142 @@
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+
+use super::{CredentialsProvider, Lease};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum JwtAlgorithm {
+    #[default]
+    RS256,
+    ES256,
+}
+
+impl From<JwtAlgorithm> for Algorithm {
+    fn from(src: JwtAlgorithm) -> Self {
+        match src {
+            JwtAlgorithm::RS256 => Algorithm::RS256,
+            JwtAlgorithm::ES256 => Algorithm::ES256,
+        }
+    }
+}
+
+/// Parameters of JWT assertions minted locally.
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtConfig {
+    /// PEM file with the private key to sign tokens with.
+    pub key_file: PathBuf,
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// Key identifier to put into the token header (`kid`).
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// Token issuer (`iss` claim).
+    pub issuer: String,
+    /// Token audience (`aud` claim).
+    pub audience: String,
+    /// Token subject (`sub` claim).
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Time each token stays valid for.
+    #[serde(with = "humantime_serde", default = "JwtConfig::def_lifetime")]
+    pub lifetime: Duration,
+}
+
+impl JwtConfig {
+    fn def_lifetime() -> Duration {
+        Duration::from_secs(300)
+    }
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    aud: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+    iat: u64,
+    exp: u64,
+}
+
+/// Credentials provider minting short-lived JWTs, sent as `Authorization: Bearer` header.
+/// A new token is minted once the previous one is about to expire, use [`JwtProvider::mint`]
+/// to get a fresh token for every request instead.
+pub struct JwtProvider {
+    key: EncodingKey,
+    header: Header,
+    config: JwtConfig,
+}
+
+impl JwtProvider {
+    pub fn new(config: JwtConfig) -> Result<Self, JwtError> {
+        let pem = std::fs::read(&config.key_file).map_err(|source| JwtError::Read {
+            path: config.key_file.clone(),
+            source,
+        })?;
+        let key = match config.algorithm {
+            JwtAlgorithm::RS256 => EncodingKey::from_rsa_pem(&pem),
+            JwtAlgorithm::ES256 => EncodingKey::from_ec_pem(&pem),
+        }
+        .map_err(JwtError::Key)?;
+        let mut header = Header::new(config.algorithm.into());
+        header.kid = config.key_id.clone();
+        Ok(Self {
+            key,
+            header,
+            config,
+        })
+    }
+
+    /// Mints a new token valid for the configured lifetime starting now.
+    pub fn mint(&self) -> Result<String, JwtError> {
+        // NOTE: system clock set before the epoch is not worth a separate error
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let claims = Claims {
+            iss: &self.config.issuer,
+            aud: &self.config.audience,
+            sub: self.config.subject.as_deref(),
+            iat: now,
+            exp: now + self.config.lifetime.as_secs(),
+        };
+        jsonwebtoken::encode(&self.header, &claims, &self.key).map_err(JwtError::Sign)
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for JwtProvider {
+    type Fail = JwtError;
+
+    async fn fetch(&self) -> Result<Lease, Self::Fail> {
+        let mut value =
+            HeaderValue::from_str(&format!("Bearer {}", self.mint()?)).map_err(JwtError::Header)?;
+        value.set_sensitive(true);
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert(AUTHORIZATION, value);
+        Ok(Lease {
+            headers,
+            duration: Some(self.config.lifetime),
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because std::io::Error doesn't implement it
+pub enum JwtError {
+    #[error("Failed reading JWT key file '{}': {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Failed parsing JWT key: {0}")]
+    Key(#[source] jsonwebtoken::errors::Error),
+    #[error("Failed signing JWT: {0}")]
+    Sign(#[source] jsonwebtoken::errors::Error),
+    #[error("Failed making header value from JWT: {0}")]
+    Header(#[source] reqwest::header::InvalidHeaderValue),
+}
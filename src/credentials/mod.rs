@@ -0,0 +1,147 @@
+#[cfg(feature = "digest")]
+pub mod digest;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+#[cfg(feature = "sigv4")]
+pub mod sigv4;
+#[cfg(feature = "vault")]
+pub mod vault;
+
+use crate::secret::Secret;
+use async_trait::async_trait;
+use cubob::{Alternate, StructShow};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    time::Duration,
+};
+
+#[cfg(feature = "digest")]
+pub use self::digest::*;
+#[cfg(feature = "jwt")]
+pub use self::jwt::*;
+#[cfg(feature = "sigv4")]
+pub use self::sigv4::*;
+#[cfg(feature = "vault")]
+pub use self::vault::*;
+
+pub const NAME_HEADER: &str = "X-API-Name";
+pub const KEY_HEADER: &str = "X-API-Key";
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub key: Secret<String>,
+}
+
+impl TryFrom<Credentials> for HeaderMap<HeaderValue> {
+    type Error = Error;
+
+    fn try_from(src: Credentials) -> Result<Self, Self::Error> {
+        let Credentials { name, key } = src;
+        let mut header_map = HeaderMap::with_capacity(2);
+        header_map.insert_from_string(NAME_HEADER, name)?;
+        header_map.insert_secret(KEY_HEADER, &key)?;
+        Ok(header_map)
+    }
+}
+
+impl Display for Credentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        StructShow::new(f, Alternate::OneLine)
+            .field(&"name", &self.name)
+            .field(&"key", &self.key)
+            .finish()
+    }
+}
+
+/// Authentication headers along with the time they stay valid for.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub headers: HeaderMap,
+    /// If None, the headers never expire, so there is no need to fetch them again.
+    pub duration: Option<Duration>,
+}
+
+impl Lease {
+    pub fn new(credentials: Credentials, duration: Option<Duration>) -> Result<Self, Error> {
+        Ok(Self {
+            headers: credentials.try_into()?,
+            duration,
+        })
+    }
+}
+
+/// External source of credentials (e.g. secrets storage), being asked for them again
+/// before the previous ones expire.
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync {
+    type Fail: std::error::Error + Send + Sync + 'static;
+
+    async fn fetch(&self) -> Result<Lease, Self::Fail>;
+
+    /// Fetches the credentials out of schedule, since the host rejected the current ones
+    /// (see [`HostConfig::refresh_on_unauthorized`](crate::HostConfig::refresh_on_unauthorized)).
+    /// Providers caching the credentials should bypass the cache here.
+    async fn refresh(&self) -> Result<Lease, Self::Fail> {
+        self.fetch().await
+    }
+}
+
+#[cfg(any(feature = "audit", feature = "digest", feature = "sigv4"))]
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+trait HeaderMapInsertString {
+    type Fail: std::error::Error;
+
+    fn insert_from_string(&mut self, key: &'static str, value: String) -> Result<(), Self::Fail>;
+
+    /// Same as `insert_from_string`, but marks the value as sensitive and never shows it in errors.
+    fn insert_secret(
+        &mut self,
+        key: &'static str,
+        value: &Secret<String>,
+    ) -> Result<(), Self::Fail>;
+}
+
+impl HeaderMapInsertString for HeaderMap<HeaderValue> {
+    type Fail = Error;
+
+    fn insert_from_string(&mut self, key: &'static str, val: String) -> Result<(), Self::Fail> {
+        let val = HeaderValue::from_str(&val).map_err(|source| Error::InvalidHeaderValue {
+            source,
+            key,
+            val,
+        })?;
+        self.insert(key, val);
+        Ok(())
+    }
+
+    fn insert_secret(&mut self, key: &'static str, val: &Secret<String>) -> Result<(), Self::Fail> {
+        let mut val =
+            HeaderValue::from_str(val.expose()).map_err(|source| Error::InvalidHeaderValue {
+                source,
+                key,
+                val: val.to_string(),
+            })?;
+        val.set_sensitive(true);
+        self.insert(key, val);
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::header::InvalidHeaderValue doesn't implement it
+pub enum Error {
+    #[error("Failed making header value for header '{key}' from text '{val}': {source}")]
+    InvalidHeaderValue {
+        source: reqwest::header::InvalidHeaderValue,
+        key: &'static str,
+        val: String,
+    },
+}
@@ -0,0 +1,234 @@
+use std::{convert::TryFrom, str::FromStr, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, RequestBuilder, Url};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{Credentials, CredentialsProvider, Lease};
+use crate::secret::Secret;
+
+const TOKEN_HEADER: &str = "X-Vault-Token";
+
+/// Version of Vault's KV secrets engine, given as `1` or `2` in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "u8")]
+pub enum KvVersion {
+    V1,
+    V2,
+}
+
+impl TryFrom<u8> for KvVersion {
+    type Error = String;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            other => Err(format!(
+                "unknown KV secrets engine version {}, expected 1 or 2",
+                other
+            )),
+        }
+    }
+}
+
+/// Parameters of the credentials stored in HashiCorp Vault's KV secrets engine.
+#[derive(Debug, Deserialize, Clone)]
+pub struct VaultConfig {
+    /// Vault server address, like `https://vault.example.com:8200`.
+    pub address: String,
+    /// Token to authenticate with.
+    pub token: Secret<String>,
+    /// Mount path of the KV secrets engine.
+    #[serde(default = "VaultConfig::def_mount")]
+    pub mount: String,
+    /// Path of the secret inside the engine.
+    pub path: String,
+    /// Version of the KV secrets engine.
+    #[serde(default = "VaultConfig::def_kv_version")]
+    pub kv_version: KvVersion,
+    /// Field of the secret containing the credentials name.
+    #[serde(default = "VaultConfig::def_name_field")]
+    pub name_field: String,
+    /// Field of the secret containing the credentials key.
+    #[serde(default = "VaultConfig::def_key_field")]
+    pub key_field: String,
+    /// Whether the token should be renewed on every fetch, so it never expires while in use.
+    #[serde(default)]
+    pub renew_token: bool,
+    /// How often to fetch secrets without lease (like any KV secret) again.
+    #[serde(with = "humantime_serde", default = "VaultConfig::def_refresh")]
+    pub refresh: Duration,
+}
+
+impl VaultConfig {
+    fn def_mount() -> String {
+        "secret".into()
+    }
+
+    fn def_kv_version() -> KvVersion {
+        KvVersion::V2
+    }
+
+    fn def_name_field() -> String {
+        "name".into()
+    }
+
+    fn def_key_field() -> String {
+        "key".into()
+    }
+
+    fn def_refresh() -> Duration {
+        Duration::from_secs(300)
+    }
+}
+
+/// Credentials provider reading them from Vault. Token lease (if renewed) and secret lease
+/// are both taken into account: the credentials are fetched again before any of them expires.
+pub struct VaultProvider {
+    client: Client,
+    base_url: Url,
+    config: VaultConfig,
+    token_lease: Mutex<Option<Duration>>,
+}
+
+impl VaultProvider {
+    pub fn new(config: VaultConfig) -> Result<Self, VaultError> {
+        let base_url = Url::from_str(&config.address).map_err(|source| VaultError::UrlParse {
+            candidate: config.address.clone(),
+            source,
+        })?;
+        let client = Client::builder().build().map_err(VaultError::ClientBuild)?;
+        Ok(Self {
+            client,
+            base_url,
+            config,
+            token_lease: Mutex::default(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        url.set_path(path);
+        self.client
+            .request(method, url)
+            .header(TOKEN_HEADER, self.config.token.expose())
+    }
+
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, VaultError> {
+        let response = request.send().await.map_err(VaultError::Request)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(VaultError::Status(status));
+        }
+        response.json().await.map_err(VaultError::Response)
+    }
+
+    async fn renew_token(&self) -> Result<(), VaultError> {
+        let answer: TokenAnswer = self
+            .call(self.request(Method::POST, "/v1/auth/token/renew-self"))
+            .await?;
+        let lease = (answer.auth.renewable && answer.auth.lease_duration > 0)
+            .then(|| Duration::from_secs(answer.auth.lease_duration));
+        *self
+            .token_lease
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = lease;
+        Ok(())
+    }
+
+    fn secret_path(&self) -> String {
+        let mount = self.config.mount.trim_matches('/');
+        let path = self.config.path.trim_start_matches('/');
+        match self.config.kv_version {
+            KvVersion::V1 => format!("/v1/{}/{}", mount, path),
+            KvVersion::V2 => format!("/v1/{}/data/{}", mount, path),
+        }
+    }
+
+    fn field(&self, data: &Map<String, Value>, field: &str) -> Result<String, VaultError> {
+        data.get(field)
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| VaultError::MissingField(field.into()))
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for VaultProvider {
+    type Fail = VaultError;
+
+    async fn fetch(&self) -> Result<Lease, Self::Fail> {
+        if self.config.renew_token {
+            self.renew_token().await?;
+        }
+        let answer: SecretAnswer = self
+            .call(self.request(Method::GET, &self.secret_path()))
+            .await?;
+        let data = match self.config.kv_version {
+            KvVersion::V1 => Some(&answer.data),
+            KvVersion::V2 => answer.data.get("data").and_then(Value::as_object),
+        }
+        .ok_or(VaultError::NoData)?;
+        let credentials = Credentials {
+            name: self.field(data, &self.config.name_field)?,
+            key: self.field(data, &self.config.key_field)?.into(),
+        };
+
+        let secret_lease = match answer.lease_duration {
+            0 => self.config.refresh,
+            seconds => Duration::from_secs(seconds),
+        };
+        let token_lease = *self
+            .token_lease
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let duration = token_lease.map_or(secret_lease, |token| token.min(secret_lease));
+        Lease::new(credentials, Some(duration)).map_err(VaultError::Credentials)
+    }
+}
+
+#[derive(Deserialize)]
+struct SecretAnswer {
+    #[serde(default)]
+    lease_duration: u64,
+    data: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct TokenAnswer {
+    auth: TokenAuth,
+}
+
+#[derive(Deserialize)]
+struct TokenAuth {
+    lease_duration: u64,
+    renewable: bool,
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
+pub enum VaultError {
+    #[error("Failed parsing Vault URL from text '{candidate}': {source}")]
+    UrlParse {
+        candidate: String,
+        source: <Url as FromStr>::Err,
+    },
+    #[error("Failed building Vault client: {0}")]
+    ClientBuild(#[source] reqwest::Error),
+    #[error("Failed sending request to Vault: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("Negative Vault response status {0}")]
+    Status(reqwest::StatusCode),
+    #[error("Failed reading Vault response: {0}")]
+    Response(#[source] reqwest::Error),
+    #[error("Vault secret contains no data")]
+    NoData,
+    #[error("Vault secret contains no string field '{0}'")]
+    MissingField(String),
+    #[error(transparent)]
+    Credentials(super::Error),
+}
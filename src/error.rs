@@ -0,0 +1,162 @@
+#[cfg(feature = "pinger")]
+use std::{
+    error::Error as StdError,
+    fmt::{Debug, Display},
+};
+
+#[cfg(feature = "pinger")]
+use crate::ping;
+use crate::{
+    address, credentials,
+    host::{self, SendError, SendErrorKind},
+};
+
+/// Any error of the crate, so applications can handle them uniformly.
+/// The wrapped errors are transparent, so their messages and sources are kept as is.
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because the wrapped errors don't implement it
+pub enum Error {
+    #[error(transparent)]
+    Address(#[from] address::Error),
+    #[error(transparent)]
+    Credentials(#[from] credentials::Error),
+    #[error(transparent)]
+    Host(#[from] host::Error),
+    #[error(transparent)]
+    Send(#[from] SendError),
+    /// Ping error with its answer failure type erased.
+    #[cfg(feature = "pinger")]
+    #[error(transparent)]
+    Ping(Box<dyn StdError + Send + Sync>),
+}
+
+/// Stable category of the [`Error`], not depending on the module it came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Configuration or arguments are invalid, so only changing them helps.
+    Config,
+    /// Connection failed or broke.
+    Transport,
+    /// No response (or its part) is received in time.
+    Timeout,
+    /// Response has negative status or answer.
+    Rejected,
+    /// Body can't be encoded or decoded.
+    Codec,
+    /// Ping failed, see the source for the details.
+    Ping,
+    /// Host accepts no new requests for now, since it is draining.
+    Unavailable,
+}
+
+#[cfg(feature = "pinger")]
+impl<R: Debug + Display + Send + Sync + 'static> From<ping::Error<R>> for Error {
+    fn from(src: ping::Error<R>) -> Self {
+        Self::Ping(Box::new(src))
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Address(_) | Self::Credentials(_) => ErrorKind::Config,
+            Self::Host(source) => host_error_kind(source),
+            Self::Send(source) => send_error_kind(&source.kind),
+            #[cfg(feature = "pinger")]
+            Self::Ping(_) => ErrorKind::Ping,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Host(source) => source.is_retryable(),
+            Self::Send(source) => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind() == ErrorKind::Timeout
+    }
+}
+
+fn send_error_kind(kind: &SendErrorKind) -> ErrorKind {
+    if kind.is_timeout() {
+        return ErrorKind::Timeout;
+    }
+    match kind {
+        SendErrorKind::Build(_) | SendErrorKind::Sign(_) => ErrorKind::Config,
+        SendErrorKind::Denied(source) => host_error_kind(source),
+        SendErrorKind::Request(_) | SendErrorKind::Body(_) | SendErrorKind::Stalled(_) => {
+            ErrorKind::Transport
+        }
+        SendErrorKind::Status(_)
+        | SendErrorKind::Rejected(_)
+        | SendErrorKind::PreconditionFailed(_) => ErrorKind::Rejected,
+        SendErrorKind::Encode(_) | SendErrorKind::Decode(_) => ErrorKind::Codec,
+        SendErrorKind::Shared(source) => send_error_kind(&source.kind),
+    }
+}
+
+/// Draining is transient, while the other host failures (egress policy denials included)
+/// are only fixed by changing the configuration or the request.
+fn host_error_kind(error: &host::Error) -> ErrorKind {
+    match error {
+        host::Error::Draining => ErrorKind::Unavailable,
+        host::Error::NamedHost { source, .. } => host_error_kind(source),
+        _ => ErrorKind::Config,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, time::Duration};
+
+    use reqwest::{Method, StatusCode};
+
+    use super::*;
+
+    #[test]
+    fn errors_are_categorized() {
+        let send = |kind| Error::from(SendError::new(Method::GET, "/", "xri", kind));
+
+        assert_eq!(
+            Error::from(host::Error::InvalidMethod("G E T".into())).kind(),
+            ErrorKind::Config
+        );
+        assert_eq!(
+            send(SendErrorKind::Status(StatusCode::CONFLICT)).kind(),
+            ErrorKind::Rejected
+        );
+        assert_eq!(
+            send(SendErrorKind::Status(StatusCode::GATEWAY_TIMEOUT)).kind(),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            send(SendErrorKind::Denied(host::Error::PolicyDenied {
+                method: Method::DELETE,
+                path: "/".into(),
+            }))
+            .kind(),
+            ErrorKind::Config
+        );
+
+        let draining = send(SendErrorKind::Denied(host::Error::Draining));
+
+        assert_eq!(draining.kind(), ErrorKind::Unavailable);
+        assert!(draining.is_retryable());
+        assert_eq!(
+            Error::from(host::Error::Draining).kind(),
+            ErrorKind::Unavailable
+        );
+
+        let stalled = send(SendErrorKind::Stalled(Duration::from_secs(1)));
+
+        assert!(stalled.is_timeout());
+        assert!(stalled.is_retryable());
+        assert!(stalled
+            .source()
+            .expect("Source of the send error should be kept")
+            .to_string()
+            .starts_with("No data received"));
+    }
+}
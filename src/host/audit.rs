@@ -0,0 +1,132 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use arc_swap::ArcSwapOption;
+use reqwest::{Request, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::{
+    send::{request_origin, SendErrorKind},
+    Labels,
+};
+use crate::credentials::{hex, NAME_HEADER};
+
+/// Record of a single outbound request. The path is hashed to keep identifiers
+/// (which paths often contain) out of the audit trail.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+    #[serde(with = "humantime_serde")]
+    pub timestamp: SystemTime,
+    pub xri: String,
+    pub method: String,
+    /// Hex-encoded SHA-256 of the request path.
+    pub path_hash: String,
+    /// Response status, if any response was received.
+    pub status: Option<u16>,
+    #[serde(with = "humantime_serde")]
+    pub latency: Duration,
+    /// Name from the credentials the request was made with.
+    pub principal: Option<String>,
+    /// Labels of the request, see [`RequestOptions::label`](super::RequestOptions::label).
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+}
+
+/// Destination of audit records. The requests are never failed because of the sink,
+/// so its own errors are up to the sink itself.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord);
+}
+
+impl AuditSink for Sender<AuditRecord> {
+    fn record(&self, record: &AuditRecord) {
+        // NOTE: the receiver being gone is not a reason to fail the request
+        let _ = self.send(record.clone());
+    }
+}
+
+/// Appends records to the file as JSON lines.
+pub struct FileSink(Mutex<File>);
+
+impl FileSink {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self(Mutex::new(file)))
+    }
+}
+
+impl AuditSink for FileSink {
+    fn record(&self, record: &AuditRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        line.push(b'\n');
+        let mut file = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // NOTE: the whole line is written at once for the records not to interleave
+        let _ = file.write_all(&line);
+    }
+}
+
+pub(crate) type AuditSlot = ArcSwapOption<Box<dyn AuditSink>>;
+
+/// Audit record data known before the request is sent.
+pub(crate) struct PendingRecord {
+    sink: Arc<Box<dyn AuditSink>>,
+    timestamp: SystemTime,
+    started: Instant,
+    xri: String,
+    method: String,
+    path_hash: String,
+    principal: Option<String>,
+    labels: Labels,
+}
+
+impl PendingRecord {
+    /// Gives nothing if there is no sink to record into.
+    pub(crate) fn new(request: &Request, slot: &AuditSlot, labels: &Labels) -> Option<Self> {
+        let sink = slot.load_full()?;
+        let (method, path, xri) = request_origin(request);
+        let principal = request
+            .headers()
+            .get(NAME_HEADER)
+            .and_then(|name| name.to_str().ok())
+            .map(Into::into);
+        Some(Self {
+            sink,
+            timestamp: SystemTime::now(),
+            started: Instant::now(),
+            xri,
+            method: method.to_string(),
+            path_hash: hex(&Sha256::digest(path.as_bytes())),
+            principal,
+            labels: labels.clone(),
+        })
+    }
+
+    pub(crate) fn finish(self, result: &Result<Response, SendErrorKind>) {
+        let status = match result {
+            Ok(response) => Some(response.status()),
+            Err(kind) => kind.status(),
+        };
+        self.sink.record(&AuditRecord {
+            timestamp: self.timestamp,
+            xri: self.xri,
+            method: self.method,
+            path_hash: self.path_hash,
+            status: status.map(|status| status.as_u16()),
+            latency: self.started.elapsed(),
+            principal: self.principal,
+            labels: self.labels,
+        });
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(feature = "digest")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(feature = "digest", feature = "sigv4"))]
+use std::{sync::Arc, time::SystemTime};
+
+#[cfg(feature = "digest")]
+use reqwest::{header::WWW_AUTHENTICATE, StatusCode};
+use reqwest::{Request, Response};
+
+use super::{SendErrorKind, Transport};
+#[cfg(feature = "sigv4")]
+use crate::credentials::SigV4Config;
+#[cfg(feature = "digest")]
+use crate::credentials::{DigestChallenge, DigestConfig};
+
+/// Authentication schemes which have to deal with the request itself rather than just
+/// add some headers to it, so they are applied right before the request execution.
+#[derive(Clone, Default)]
+pub(crate) struct Auth {
+    #[cfg(feature = "digest")]
+    pub digest: Option<Arc<DigestConfig>>,
+    #[cfg(feature = "sigv4")]
+    pub sigv4: Option<Arc<SigV4Config>>,
+}
+
+impl Auth {
+    /// Executes the request, counting the requests really sent to the host into `attempts`.
+    pub async fn execute<T: Transport>(
+        &self,
+        transport: &T,
+        request: Request,
+        attempts: &mut u32,
+    ) -> Result<Response, SendErrorKind> {
+        #[cfg(feature = "digest")]
+        let repeated = self.digest.as_ref().and_then(|_| request.try_clone());
+        #[cfg(feature = "sigv4")]
+        let request = self.sign(request)?;
+
+        *attempts += 1;
+        let response = transport.execute(request).await?;
+
+        #[cfg(feature = "digest")]
+        if let (Some(digest), Some(mut repeated)) = (&self.digest, repeated) {
+            let challenge = response
+                .headers()
+                .get_all(WWW_AUTHENTICATE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .find_map(DigestChallenge::parse);
+            if let (StatusCode::UNAUTHORIZED, Some(challenge)) = (response.status(), challenge) {
+                let url = repeated.url();
+                let uri = match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_owned(),
+                };
+                let authorization = digest
+                    .authorize(&challenge, repeated.method(), &uri, &cnonce())
+                    .map_err(SendErrorKind::Sign)?;
+                repeated.headers_mut().extend(authorization);
+                *attempts += 1;
+                return transport.execute(repeated).await;
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "sigv4")]
+impl Auth {
+    fn sign(&self, mut request: Request) -> Result<Request, SendErrorKind> {
+        if let Some(sigv4) = &self.sigv4 {
+            sigv4
+                .sign(&mut request, SystemTime::now())
+                .map_err(SendErrorKind::Sign)?;
+        }
+        Ok(request)
+    }
+}
+
+/// Client nonce, unique enough to prevent chosen plaintext attacks.
+#[cfg(feature = "digest")]
+fn cnonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    format!(
+        "{:016x}{:08x}",
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, CACHE_CONTROL, DATE, ETAG, EXPIRES, IF_MODIFIED_SINCE,
+        IF_NONE_MATCH, LAST_MODIFIED,
+    },
+    RequestBuilder,
+};
+use serde::Deserialize;
+
+use super::FullResponse;
+
+/// Response kept in cache along with its validators.
+pub type CachedResponse = FullResponse;
+
+impl CachedResponse {
+    pub fn last_modified(&self) -> Option<&HeaderValue> {
+        self.headers.get(LAST_MODIFIED)
+    }
+
+    /// Whether the response has any validator or freshness lifetime, so it makes sense to keep it.
+    pub fn is_cacheable(&self) -> bool {
+        self.status.is_success()
+            && !self.has_directive("no-store")
+            && (self.etag().is_some()
+                || self.last_modified().is_some()
+                || self.freshness_lifetime().is_some())
+    }
+
+    /// Time the response stays fresh for since its Date, given by the `max-age` directive
+    /// of Cache-Control or by the Expires header (None if neither is present or valid).
+    pub fn freshness_lifetime(&self) -> Option<Duration> {
+        let max_age = self.directives().find_map(|directive| {
+            let (name, value) = directive.split_once('=')?;
+            if !name.trim().eq_ignore_ascii_case("max-age") {
+                return None;
+            }
+            value.trim().trim_matches('"').parse().ok()
+        });
+        if let Some(max_age) = max_age {
+            return Some(Duration::from_secs(max_age));
+        }
+        let expires = httpdate::parse_http_date(self.headers.get(EXPIRES)?.to_str().ok()?).ok()?;
+        Some(expires.duration_since(self.date()?).unwrap_or_default())
+    }
+
+    /// Whether the response may be served without asking the server, i.e. it is not older
+    /// than its freshness lifetime and is not required to be revalidated every time.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        if self.has_directive("no-cache") {
+            return false;
+        }
+        match (self.date(), self.freshness_lifetime()) {
+            // NOTE: the lifetime too long to be represented means the response never expires
+            (Some(date), Some(lifetime)) => date
+                .checked_add(lifetime)
+                .is_none_or(|expired| now < expired),
+            _ => false,
+        }
+    }
+
+    /// Whether the response is not fresh, but may still be served while being revalidated,
+    /// i.e. it is stale for less than the window and is not required to be revalidated first.
+    pub fn is_stale_usable(&self, now: SystemTime, window: Duration) -> bool {
+        if self.has_directive("no-cache") || self.has_directive("must-revalidate") {
+            return false;
+        }
+        match (self.date(), self.freshness_lifetime()) {
+            // NOTE: the response which never expires is never stale, the window too long to be
+            // represented lets the stale response be used forever
+            (Some(date), Some(lifetime)) => date.checked_add(lifetime).is_some_and(|expired| {
+                expired <= now
+                    && expired
+                        .checked_add(window)
+                        .is_none_or(|usable| now < usable)
+            }),
+            _ => false,
+        }
+    }
+
+    /// Adds conditional headers based on the validators of this response.
+    pub fn condition(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = self.etag() {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = self.last_modified() {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+
+    /// Takes the freshness and validator headers of the 304 response revalidating this one.
+    pub fn refresh(&mut self, headers: &HeaderMap) {
+        for name in [DATE, CACHE_CONTROL, EXPIRES, ETAG, LAST_MODIFIED] {
+            if let Some(value) = headers.get(&name) {
+                self.headers.insert(name, value.clone());
+            }
+        }
+    }
+
+    fn date(&self) -> Option<SystemTime> {
+        httpdate::parse_http_date(self.headers.get(DATE)?.to_str().ok()?).ok()
+    }
+
+    fn directives(&self) -> impl Iterator<Item = &str> {
+        self.headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+    }
+
+    fn has_directive(&self, name: &str) -> bool {
+        self.directives()
+            .any(|directive| directive.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Response cache settings of the host.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CacheConfig {
+    /// The most responses kept, the oldest ones are dropped first (not limited if None).
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+    /// Path prefixes never served from (nor stored to) the cache.
+    #[serde(default)]
+    pub bypass: Vec<String>,
+    /// Window after the freshness lifetime within which the stale response is returned at once,
+    /// being revalidated in the background (requires the `tokio` feature, ignored otherwise).
+    #[serde(default, with = "humantime_serde")]
+    pub stale_while_revalidate: Option<Duration>,
+}
+
+impl CacheConfig {
+    pub fn bypasses(&self, path: &str) -> bool {
+        self.bypass.iter().any(|prefix| path.starts_with(prefix))
+    }
+}
+
+/// Storage for cached responses, keyed by request path.
+pub trait CacheStore: Send + Sync + 'static {
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    fn put(&self, key: &str, response: CachedResponse);
+
+    /// Makes the store following the host config (the default store is made by default).
+    fn from_config(_config: &CacheConfig) -> Self
+    where
+        Self: Sized + Default,
+    {
+        Self::default()
+    }
+}
+
+/// Store which never keeps anything, effectively disabling the cache.
+#[derive(Default)]
+pub struct NoCache;
+
+impl CacheStore for NoCache {
+    fn get(&self, _key: &str) -> Option<CachedResponse> {
+        None
+    }
+
+    fn put(&self, _key: &str, _response: CachedResponse) {}
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, CachedResponse>,
+    /// Keys in the order of insertion, to drop the oldest ones.
+    order: VecDeque<String>,
+}
+
+/// In-memory store, unbounded unless made from the config with [`CacheConfig::max_entries`].
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<Entries>,
+    max_entries: Option<usize>,
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .map
+            .get(key)
+            .cloned()
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if entries.map.insert(key.into(), response).is_some() {
+            return;
+        }
+        entries.order.push_back(key.into());
+        let max_entries = self.max_entries.unwrap_or(usize::MAX);
+        while entries.map.len() > max_entries {
+            match entries.order.pop_front() {
+                Some(oldest) => entries.map.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn from_config(config: &CacheConfig) -> Self {
+        Self {
+            entries: Mutex::default(),
+            max_entries: config.max_entries,
+        }
+    }
+}
@@ -3,7 +3,14 @@ use std::{
     time::Duration,
 };
 
-use reqwest::Method;
+use reqwest::{header::HeaderMap, Method, StatusCode};
+
+#[cfg(feature = "tls-expiry")]
+use super::CertificateInfo;
+use super::{ClockSkew, Labels, RequestSpec, SendError};
+
+#[cfg(feature = "pinger")]
+use crate::ping::Health;
 
 #[derive(Clone, Debug)]
 pub struct RequestInfo<'a> {
@@ -11,6 +18,8 @@ pub struct RequestInfo<'a> {
     pub path: &'a str,
     pub timeout: Duration,
     pub xri: Option<&'a str>,
+    /// Labels of the request, see [`RequestOptions::label`](super::RequestOptions::label).
+    pub labels: &'a Labels,
 }
 
 impl Display for RequestInfo<'_> {
@@ -23,16 +32,74 @@ impl Display for RequestInfo<'_> {
                 &humantime_serde::re::humantime::format_duration(self.timeout),
             )
             .field_opt(&"xri", &self.xri)
+            .field_opt(
+                &"labels",
+                &Some(self.labels).filter(|labels| !labels.is_empty()),
+            )
             .finish()
     }
 }
 
 pub trait Callbacks {
-    fn on_request_building(request_info: &RequestInfo);
+    /// Per-request data (like timers or correlation ids) created when the request is being built
+    /// and passed to the later hooks of the same request.
+    type Context: Send;
+
+    fn on_request_building(request_info: &RequestInfo) -> Self::Context;
+
+    /// Called once the response head is received (or the sending failed) for every request sent
+    /// through the [`Host::send`](super::Host::send) family of methods. Requests built by the user
+    /// and sent by other means never reach it.
+    fn on_response_received(_context: Self::Context, _result: Result<StatusCode, &SendError>) {}
+
+    /// Called right before [`Callbacks::on_response_received`] with the response headers listed in
+    /// [`HostConfig::correlation_headers`](super::HostConfig::correlation_headers), if any of them are received.
+    fn on_response_correlated(_context: &Self::Context, _correlation: &HeaderMap) {}
+
+    /// Called for every request built by the host with its spec, if requests are inspected
+    /// (see [`HostConfig::inspect_requests`](super::HostConfig::inspect_requests)).
+    fn on_request_inspected(_spec: &RequestSpec) {}
+
+    /// Called when the host clock skew estimated from a response exceeds the configured threshold
+    /// (see [`ClockSkewConfig`](super::ClockSkewConfig)).
+    fn on_clock_skew(_skew: ClockSkew) {}
+
+    /// Called once per host certificate when it expires within the configured window
+    /// (see [`CertificateExpiryConfig`](super::CertificateExpiryConfig)).
+    #[cfg(feature = "tls-expiry")]
+    fn on_certificate_expiring(_info: &CertificateInfo) {}
+
+    /// Called when the host is looked up in the [`HostRegistry`](super::HostRegistry) by the
+    /// deprecated alias (see [`HostRegistry::alias`](super::HostRegistry::alias)), e.g. to warn
+    /// the code still using it.
+    fn on_alias_used(_alias: &str, _name: &str) {}
+
+    /// Called right before every ping request is sent.
+    #[cfg(feature = "pinger")]
+    fn on_ping_started() {}
+
+    /// Called after every successful ping with the time it took.
+    #[cfg(feature = "pinger")]
+    fn on_ping_succeeded(_latency: Duration) {}
+
+    /// Called after every failed ping with the count of failures in a row (including this one)
+    /// and the request ID the ping was sent with.
+    #[cfg(feature = "pinger")]
+    fn on_ping_failed(_error: &dyn Display, _consecutive: u32, _xri: &str) {}
+
+    /// Called when the ping took longer than its period, so the next one is made right after it.
+    #[cfg(feature = "pinger")]
+    fn on_ping_overran(_elapsed: Duration, _period: Duration) {}
+
+    /// Called when ping results change the known host health.
+    #[cfg(feature = "pinger")]
+    fn on_health_changed(_old: Health, _new: Health) {}
 }
 
 pub struct TrivialCallbacks;
 
 impl Callbacks for TrivialCallbacks {
-    fn on_request_building(_request_info: &RequestInfo) {}
+    type Context = ();
+
+    fn on_request_building(_request_info: &RequestInfo) -> Self::Context {}
 }
@@ -0,0 +1,161 @@
+use std::{
+    convert::TryFrom,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{tls::TlsInfo, Response};
+use serde::{Deserialize, Serialize};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Monitoring of the host TLS certificate expiry, observed on the responses (and probed with
+/// [`Host::probe_certificate`](super::Host::probe_certificate)). Note that only the leaf
+/// certificate is known, since reqwest gives no access to the rest of the chain.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CertificateExpiryConfig {
+    /// Time before the expiry since which the callbacks are warned (once per certificate).
+    #[serde(
+        with = "humantime_serde",
+        default = "CertificateExpiryConfig::def_warn_within"
+    )]
+    pub warn_within: Duration,
+}
+
+impl CertificateExpiryConfig {
+    pub fn def_warn_within() -> Duration {
+        Duration::from_secs(14 * 24 * 60 * 60)
+    }
+}
+
+impl Default for CertificateExpiryConfig {
+    fn default() -> Self {
+        Self {
+            warn_within: Self::def_warn_within(),
+        }
+    }
+}
+
+/// Server certificate seen on the latest TLS connection to the host.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    #[serde(with = "humantime_serde")]
+    pub not_after: SystemTime,
+}
+
+impl CertificateInfo {
+    /// Gives None if the DER is not a valid X.509 certificate.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+        let not_after = certificate.validity().not_after.timestamp();
+        Some(Self {
+            subject: certificate.subject().to_string(),
+            not_after: UNIX_EPOCH + Duration::from_secs(u64::try_from(not_after).ok()?),
+        })
+    }
+
+    /// Time left till the expiry, zero if already expired.
+    pub fn remaining(&self) -> Duration {
+        self.not_after
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+struct Observed {
+    der: Vec<u8>,
+    info: CertificateInfo,
+    warned: bool,
+}
+
+/// Keeps the certificate of the latest TLS connection the response was received through.
+pub(crate) struct CertificateWatch {
+    warn_within: Duration,
+    last: Mutex<Option<Observed>>,
+}
+
+impl CertificateWatch {
+    pub(crate) fn new(config: CertificateExpiryConfig) -> Self {
+        Self {
+            warn_within: config.warn_within,
+            last: Mutex::default(),
+        }
+    }
+
+    /// Gives the certificate of the response connection if it expires soon (once per certificate).
+    pub(crate) fn sample(&self, response: &Response) -> Option<CertificateInfo> {
+        self.observe(response.extensions().get::<TlsInfo>()?.peer_certificate()?)
+    }
+
+    fn observe(&self, der: &[u8]) -> Option<CertificateInfo> {
+        let mut last = self
+            .last
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // NOTE: the same certificate is mostly seen again, so it is parsed only once
+        if last.as_ref().is_none_or(|observed| observed.der != der) {
+            *last = Some(Observed {
+                der: der.to_vec(),
+                info: CertificateInfo::from_der(der)?,
+                warned: false,
+            });
+        }
+        let observed = last.as_mut()?;
+        if observed.warned || observed.info.remaining() > self.warn_within {
+            return None;
+        }
+        observed.warned = true;
+        Some(observed.info.clone())
+    }
+
+    pub(crate) fn last(&self) -> Option<CertificateInfo> {
+        self.last
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .map(|observed| observed.info.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::pem::parse_x509_pem;
+
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBfjCCASSgAwIBAgIBATAKBggqhkjOPQQDAjAeMRwwGgYDVQQDDBNiaWxsaW5n
+LmV4YW1wbGUuY29tMB4XDTI2MTAxNzA4MTc1N1oXDTM2MTAxNDA4MTc1N1owHjEc
+MBoGA1UEAwwTYmlsbGluZy5leGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqGSM49
+AwEHA0IABAQqNU2glWX1wn1xdGWGiNfhitwIzuzCdURP0fz2qKq3NpLQ6iShn3Zz
+vL2MvNYOBrj7x2T3B7nq0YTbTrc6pd2jUzBRMB0GA1UdDgQWBBR9w0dz9a0WBV4a
+LNpwVMVEU35cjjAfBgNVHSMEGDAWgBR9w0dz9a0WBV4aLNpwVMVEU35cjjAPBgNV
+HRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIQCVex8GiX7k8mpYMMygHODm
+MK9+H5rVzS8Y9bFNShatJwIgePkpitfUxW6nOvKGEd0IOv44ZaqcjUsPEZLdUNzI
+UUs=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn expiring_certificates_are_warned_once() {
+        let (_, pem) = parse_x509_pem(CERTIFICATE.as_bytes()).expect("PEM should be parsed");
+        let expected = CertificateInfo {
+            subject: "CN=billing.example.com".into(),
+            // NOTE: Oct 14 08:17:57 2036 GMT
+            not_after: UNIX_EPOCH + Duration::from_secs(2107585077),
+        };
+
+        let relaxed = CertificateWatch::new(CertificateExpiryConfig::default());
+        assert_eq!(relaxed.observe(&pem.contents), None);
+        assert_eq!(relaxed.last(), Some(expected.clone()));
+
+        let strict = CertificateWatch::new(CertificateExpiryConfig {
+            warn_within: Duration::from_secs(100 * 365 * 24 * 60 * 60),
+        });
+        assert_eq!(strict.observe(&pem.contents), Some(expected.clone()));
+        assert_eq!(strict.observe(&pem.contents), None);
+        // NOTE: unparseable certificates don't replace the last known one
+        assert_eq!(strict.observe(b"not a certificate"), None);
+        assert_eq!(strict.last(), Some(expected));
+    }
+}
@@ -0,0 +1,38 @@
+use std::hash::Hash;
+
+use reqwest::{Client, ClientBuilder};
+
+use super::{Error, HostConfig};
+
+/// Maker of the reqwest client of the host, for the builder options the config doesn't cover.
+pub trait ClientFactory {
+    /// Builds the client given the builder with the user agent (and the DNS resolver
+    /// if `dns-timings` feature is enabled, the pinned TLS config if `tls-pinning` one is,
+    /// the identity if configured) already set. Called again on every identity reload.
+    fn build<K: Eq + Hash + Default>(
+        builder: ClientBuilder,
+        config: &HostConfig<K>,
+    ) -> Result<Client, Error>;
+}
+
+/// Applies the extra settings and the scheme strictness of the config.
+pub struct DefaultClientFactory;
+
+impl ClientFactory for DefaultClientFactory {
+    fn build<K: Eq + Hash + Default>(
+        mut builder: ClientBuilder,
+        config: &HostConfig<K>,
+    ) -> Result<Client, Error> {
+        if let Some(es) = config.extras.clone() {
+            builder = es.apply(builder);
+        }
+        builder
+            .https_only(
+                config
+                    .strict_scheme
+                    .unwrap_or_else(|| config.scheme.is_secure()),
+            )
+            .build()
+            .map_err(Error::ClientBulid)
+    }
+}
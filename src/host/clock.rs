@@ -0,0 +1,90 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::{HeaderMap, DATE};
+use serde::{Deserialize, Serialize};
+
+/// Estimation of the host clock skew from the Date headers of the responses, which is worth
+/// enabling for the hosts taking signed requests (as the signatures expire).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClockSkewConfig {
+    /// Skew beyond which the callbacks are warned. Note that the Date header has whole seconds only,
+    /// so the skew of a second or two is usually just the latency.
+    #[serde(with = "humantime_serde", default = "ClockSkewConfig::def_threshold")]
+    pub threshold: Duration,
+}
+
+impl ClockSkewConfig {
+    pub fn def_threshold() -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+impl Default for ClockSkewConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Self::def_threshold(),
+        }
+    }
+}
+
+/// Difference of the host clock from the local one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockSkew {
+    Ahead(#[serde(with = "humantime_serde")] Duration),
+    Behind(#[serde(with = "humantime_serde")] Duration),
+}
+
+impl ClockSkew {
+    pub fn between(remote: SystemTime, local: SystemTime) -> Self {
+        match remote.duration_since(local) {
+            Ok(ahead) => Self::Ahead(ahead),
+            Err(behind) => Self::Behind(behind.duration()),
+        }
+    }
+
+    pub fn magnitude(&self) -> Duration {
+        match self {
+            Self::Ahead(skew) | Self::Behind(skew) => *skew,
+        }
+    }
+}
+
+/// Keeps the skew estimated from the last response having a valid Date header.
+pub(crate) struct ClockWatch {
+    threshold: Duration,
+    last: Mutex<Option<ClockSkew>>,
+}
+
+impl ClockWatch {
+    pub(crate) fn new(config: ClockSkewConfig) -> Self {
+        Self {
+            threshold: config.threshold,
+            last: Mutex::default(),
+        }
+    }
+
+    /// Gives the skew if it exceeds the threshold.
+    pub(crate) fn sample(&self, headers: &HeaderMap) -> Option<ClockSkew> {
+        let local = SystemTime::now();
+        let remote = httpdate::parse_http_date(headers.get(DATE)?.to_str().ok()?).ok()?;
+        // NOTE: the local time is truncated to whole seconds as well, not to count the fraction as skew
+        let local_secs = local.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let skew = ClockSkew::between(remote, UNIX_EPOCH + Duration::from_secs(local_secs));
+        *self
+            .last
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(skew);
+        (skew.magnitude() > self.threshold).then_some(skew)
+    }
+
+    pub(crate) fn last(&self) -> Option<ClockSkew> {
+        *self
+            .last
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
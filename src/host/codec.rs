@@ -0,0 +1,132 @@
+use std::error::Error as StdError;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub type CodecError = Box<dyn StdError + Send + Sync>;
+
+/// Body format of requests and responses, selected per call in [`Host::send_as`](super::Host::send_as)
+/// and alike.
+pub trait Codec {
+    /// Value for both Content-Type and Accept headers.
+    const CONTENT_TYPE: &'static str;
+}
+
+pub trait Encode<T: ?Sized>: Codec {
+    fn encode(value: &T) -> Result<Vec<u8>, CodecError>;
+}
+
+pub trait Decode<T>: Codec {
+    fn decode(body: &[u8]) -> Result<T, CodecError>;
+}
+
+pub struct Json;
+
+impl Codec for Json {
+    const CONTENT_TYPE: &'static str = "application/json";
+}
+
+impl<T: Serialize + ?Sized> Encode<T> for Json {
+    fn encode(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
+
+impl<T: DeserializeOwned> Decode<T> for Json {
+    fn decode(body: &[u8]) -> Result<T, CodecError> {
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+/// UTF-8 plain text, as is.
+pub struct Text;
+
+impl Codec for Text {
+    const CONTENT_TYPE: &'static str = "text/plain; charset=utf-8";
+}
+
+impl Encode<str> for Text {
+    fn encode(value: &str) -> Result<Vec<u8>, CodecError> {
+        Ok(value.as_bytes().to_vec())
+    }
+}
+
+impl Encode<String> for Text {
+    fn encode(value: &String) -> Result<Vec<u8>, CodecError> {
+        Ok(value.as_bytes().to_vec())
+    }
+}
+
+impl Decode<String> for Text {
+    fn decode(body: &[u8]) -> Result<String, CodecError> {
+        Ok(std::str::from_utf8(body)?.to_owned())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePack {
+    const CONTENT_TYPE: &'static str = "application/msgpack";
+}
+
+#[cfg(feature = "msgpack")]
+impl<T: Serialize + ?Sized> Encode<T> for MessagePack {
+    fn encode(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(rmp_serde::to_vec_named(value)?)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<T: DeserializeOwned> Decode<T> for MessagePack {
+    fn decode(body: &[u8]) -> Result<T, CodecError> {
+        Ok(rmp_serde::from_slice(body)?)
+    }
+}
+
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+}
+
+#[cfg(feature = "cbor")]
+impl<T: Serialize + ?Sized> Encode<T> for Cbor {
+    fn encode(value: &T) -> Result<Vec<u8>, CodecError> {
+        let mut body = Vec::new();
+        ciborium::into_writer(value, &mut body)?;
+        Ok(body)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T: DeserializeOwned> Decode<T> for Cbor {
+    fn decode(body: &[u8]) -> Result<T, CodecError> {
+        Ok(ciborium::from_reader(body)?)
+    }
+}
+
+/// Protocol Buffers messages (as used by gRPC-adjacent services exposing protobuf-over-HTTP).
+#[cfg(feature = "protobuf")]
+pub struct Protobuf;
+
+#[cfg(feature = "protobuf")]
+impl Codec for Protobuf {
+    const CONTENT_TYPE: &'static str = "application/x-protobuf";
+}
+
+#[cfg(feature = "protobuf")]
+impl<T: prost::Message> Encode<T> for Protobuf {
+    fn encode(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(value.encode_to_vec())
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl<T: prost::Message + Default> Decode<T> for Protobuf {
+    fn decode(body: &[u8]) -> Result<T, CodecError> {
+        Ok(T::decode(body)?)
+    }
+}
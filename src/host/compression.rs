@@ -0,0 +1,80 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+use reqwest::{
+    header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE},
+    Request,
+};
+use serde::Deserialize;
+
+/// Gzip compression of the request bodies, applied to the bodies of the listed content types
+/// being not less than the threshold. Requests already having Content-Encoding are left as is,
+/// so a request opts out with [`RequestOptions::uncompressed`](super::RequestOptions::uncompressed).
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompressionConfig {
+    /// Body size (in bytes) the compression starts from.
+    #[serde(default = "CompressionConfig::def_threshold")]
+    pub threshold: usize,
+    /// Content types (without parameters) of the bodies to compress.
+    #[serde(default = "CompressionConfig::def_content_types")]
+    pub content_types: Vec<String>,
+}
+
+impl CompressionConfig {
+    pub fn def_threshold() -> usize {
+        1024
+    }
+
+    pub fn def_content_types() -> Vec<String> {
+        vec!["application/json".into()]
+    }
+
+    fn is_suitable(&self, request: &Request) -> bool {
+        if request.headers().contains_key(CONTENT_ENCODING) {
+            return false;
+        }
+        let content_type = match request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(value) => value.split(';').next().unwrap_or_default().trim(),
+            None => return false,
+        };
+        self.content_types
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(content_type))
+    }
+
+    /// Compresses the body of the request if it is suitable. Streamed bodies are never compressed,
+    /// and the compression failure just leaves the body as is.
+    pub(crate) fn apply(&self, request: &mut Request) {
+        if !self.is_suitable(request) {
+            return;
+        }
+        let compressed = match request.body().and_then(|body| body.as_bytes()) {
+            Some(body) if body.len() >= self.threshold => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                match encoder.write_all(body).and_then(|_| encoder.finish()) {
+                    Ok(compressed) => compressed,
+                    Err(_) => return,
+                }
+            }
+            _ => return,
+        };
+        let headers = request.headers_mut();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        // NOTE: the length is set by the client again according to the new body
+        headers.remove(CONTENT_LENGTH);
+        *request.body_mut() = Some(compressed.into());
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Self::def_threshold(),
+            content_types: Self::def_content_types(),
+        }
+    }
+}
@@ -1,10 +1,38 @@
-use std::{hash::Hash, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    hash::Hash,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use reqwest::ClientBuilder;
+use humantime_serde::re::humantime::format_duration;
+
+use reqwest::{ClientBuilder, Url};
 use serde::Deserialize;
 
+use super::{base_url, ClockSkewConfig, EgressPolicy, Error};
 use crate::{address::Address, credentials::Credentials, timeoutsmap::TimeoutsMapConfig, Scheme};
 
+#[cfg(feature = "cache")]
+use super::CacheConfig;
+#[cfg(feature = "tls-expiry")]
+use super::CertificateExpiryConfig;
+#[cfg(feature = "gzip")]
+use super::CompressionConfig;
+#[cfg(feature = "digest")]
+use crate::credentials::DigestConfig;
+#[cfg(feature = "sigv4")]
+use crate::credentials::SigV4Config;
 #[cfg(feature = "pinger")]
 use crate::ping;
 
@@ -13,25 +41,250 @@ pub struct HostConfig<K: Eq + Hash + Default> {
     /// Credentials to use for authentication (only X-API headers are currently supported).
     #[serde(default, flatten)]
     pub credentials: Option<Credentials>,
+    /// Named credentials to be selected per request with [`RequestOptions::credentials`](super::RequestOptions::credentials)
+    /// instead of (or in addition to) the default ones.
+    #[serde(default)]
+    pub credential_sets: HashMap<String, Credentials>,
+    /// Whether a request rejected with 401 Unauthorized is repeated once with the credentials refreshed
+    /// by the provider (see [`spawn_provider`](super::spawn_provider)), so the expired tokens are
+    /// replaced transparently. Requests made with the other credential sets are never repeated.
+    #[cfg(feature = "tokio")]
+    #[serde(default)]
+    pub refresh_on_unauthorized: bool,
+    /// Named sets of static headers (like Accept-Language or API version pinning ones).
+    #[serde(default)]
+    pub profiles: HashMap<String, HashMap<String, String>>,
+    /// Name of the profile which headers are added to every request.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Accept header of every request (like `application/vnd.foo.v2+json`), overriding
+    /// the codec content type; a request may override it with [`RequestOptions::accept`](super::RequestOptions::accept).
+    #[serde(default)]
+    pub accept: Option<String>,
+    /// Accept-Encoding header of every request, overridable with [`RequestOptions::accept_encoding`](super::RequestOptions::accept_encoding).
+    /// Note that responses are not decompressed by the client, so only the identity encoding is handled transparently.
+    #[serde(default)]
+    pub accept_encoding: Option<String>,
+    /// Response headers (like `X-Request-Id` or `X-Trace-Id`) to capture into the [`SendReport`](super::SendReport)
+    /// and the callbacks, so the client and the server logs can be joined even if the server rewrites the IDs.
+    #[serde(default)]
+    pub correlation_headers: Vec<String>,
+    /// Opt-in estimation of the host clock skew, see [`Host::runtime_info`](super::Host::runtime_info).
+    #[serde(default)]
+    pub clock_skew: Option<ClockSkewConfig>,
+    /// Opt-in monitoring of the host TLS certificate expiry, see [`Host::health_report`](super::Host::health_report).
+    /// Works only for the clients built by the host, since the TLS info is to be enabled in the client.
+    #[cfg(feature = "tls-expiry")]
+    #[serde(default)]
+    pub tls_expiry: Option<CertificateExpiryConfig>,
+    /// Debug mode: number of the latest requests built by the host to keep as inspectable specs
+    /// (see [`Host::inspected_requests`](super::Host::inspected_requests)), also passed to the callbacks.
+    /// Costs a copy of every request, so better keep it off in production.
+    #[serde(default)]
+    pub inspect_requests: Option<usize>,
+    /// Time the requests sent with [`Host::send_deduped`](super::Host::send_deduped) are remembered by their
+    /// methods, paths and idempotency keys, so the duplicates sent within it get the response
+    /// of the original one instead.
+    #[serde(default, with = "humantime_serde")]
+    pub dedupe_window: Option<Duration>,
+    #[cfg(feature = "gzip")]
+    /// Compression of the request bodies, if enabled.
+    #[serde(default)]
+    pub compression: Option<CompressionConfig>,
+    #[cfg(feature = "cache")]
+    /// Response cache settings, see [`Host::get_cached`](super::Host::get_cached).
+    #[serde(default)]
+    pub cache: CacheConfig,
     /// Terget host address (IP or DNS-name and port separated with semicolon).
     #[serde(default)]
     pub target: Address,
     /// Scheme used to interact with the host (all requests will use that scheme).
     #[serde(default)]
     pub scheme: Scheme,
+    /// Whether the client should refuse any non-HTTPS requests (including redirects).
+    /// If None, it is derived from the scheme: only secure schemes are strict. Note that
+    /// the requests with the scheme overridden (like the pings with their own scheme) are refused
+    /// too, so a secure host sending them over plain HTTP has to be set non-strict explicitly.
+    #[serde(default)]
+    pub strict_scheme: Option<bool>,
+    /// Client certificate the connections are authenticated with (mTLS), reloadable on the fly
+    /// (see [`Host::reload_identity`](super::Host::reload_identity)).
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[serde(default)]
+    pub identity: Option<IdentityConfig>,
+    /// Methods and paths the host is allowed to be requested with, if restricted. Not checked
+    /// for the requests built with the infallible builders, see [`EgressPolicy`].
+    #[serde(default)]
+    pub egress: Option<EgressPolicy>,
     #[serde(default)]
     /// Timeouts map for different request types (depends on K type parameter).
     pub timeouts: TimeoutsMapConfig<K>,
+    /// Timeouts keys to use for requests made without explicit one, chosen by request method.
+    #[serde(default)]
+    pub method_timeouts: MethodTimeouts<K>,
+    #[cfg(feature = "audit")]
+    /// File to append the audit records of every request to.
+    #[serde(default)]
+    pub audit_file: Option<std::path::PathBuf>,
     #[cfg(feature = "pinger")]
     /// Autometed pinger configuration.
     #[serde(default)]
     pub ping: Option<ping::Config>,
+    #[cfg(feature = "pinger")]
+    /// Keepalive requests configuration (preventing pooled connections from being closed as idle).
+    #[serde(default)]
+    pub keepalive: Option<ping::KeepaliveConfig>,
+    #[cfg(feature = "digest")]
+    /// HTTP Digest authentication credentials, used to answer the server challenges.
+    #[serde(default)]
+    pub digest: Option<DigestConfig>,
+    #[cfg(feature = "sigv4")]
+    /// AWS SigV4 signing credentials and scope.
+    #[serde(default)]
+    pub sigv4: Option<SigV4Config>,
     /// Extra settings to pass into related reqwest's ClientBuilder methods. If None, default reqwest's parameters are being kept.
     /// If not None, but empty (i.e. empty section in the config) provides its own defaults!
     #[serde(default)]
     pub extras: Option<ExtraSettings>,
 }
 
+impl<K: Eq + Hash + Default> HostConfig<K> {
+    /// Copy of the config to rebuild the client with: the secrets are left out, as well as the
+    /// timeouts (keyed with `K`, which is not necessarily cloneable) the client doesn't depend on.
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    pub(crate) fn client_part(&self) -> Self {
+        Self {
+            credentials: None,
+            credential_sets: HashMap::new(),
+            #[cfg(feature = "tokio")]
+            refresh_on_unauthorized: self.refresh_on_unauthorized,
+            profiles: self.profiles.clone(),
+            profile: self.profile.clone(),
+            accept: self.accept.clone(),
+            accept_encoding: self.accept_encoding.clone(),
+            correlation_headers: self.correlation_headers.clone(),
+            clock_skew: self.clock_skew.clone(),
+            #[cfg(feature = "tls-expiry")]
+            tls_expiry: self.tls_expiry.clone(),
+            inspect_requests: self.inspect_requests,
+            dedupe_window: self.dedupe_window,
+            #[cfg(feature = "gzip")]
+            compression: self.compression.clone(),
+            #[cfg(feature = "cache")]
+            cache: self.cache.clone(),
+            target: self.target.clone(),
+            scheme: self.scheme,
+            strict_scheme: self.strict_scheme,
+            identity: self.identity.clone(),
+            egress: self.egress.clone(),
+            timeouts: Default::default(),
+            method_timeouts: Default::default(),
+            #[cfg(feature = "audit")]
+            audit_file: self.audit_file.clone(),
+            #[cfg(feature = "pinger")]
+            ping: self.ping.clone(),
+            #[cfg(feature = "pinger")]
+            keepalive: self.keepalive.clone(),
+            #[cfg(feature = "digest")]
+            digest: None,
+            #[cfg(feature = "sigv4")]
+            sigv4: None,
+            extras: self.extras.clone(),
+        }
+    }
+
+    /// Base URL the host would use with this config, without building any client,
+    /// e.g. to verify the config beforehand.
+    pub fn preview_base_url(&self) -> Result<Url, Error> {
+        base_url(self.scheme.into(), self.target.clone())
+    }
+
+    /// Timeouts of the map exceeding the host-wide request timeout (if any), as they are cut down to it
+    /// and so most likely are misconfigured. The default timeout is reported with no key.
+    pub fn timeout_warnings(&self) -> Vec<TimeoutWarning<'_, K>> {
+        let ceiling = match self
+            .extras
+            .as_ref()
+            .and_then(|extras| extras.request_timeout)
+        {
+            Some(ceiling) => ceiling,
+            None => return Vec::new(),
+        };
+        let default = (None, self.timeouts.default);
+        let keyed = self
+            .timeouts
+            .map
+            .iter()
+            .filter_map(|(spec, entry)| Some((Some(spec), entry.timeout()?)));
+        std::iter::once(default)
+            .chain(keyed)
+            .filter(|(_, timeout)| *timeout > ceiling)
+            .map(|(spec, timeout)| TimeoutWarning {
+                spec,
+                timeout,
+                ceiling,
+            })
+            .collect()
+    }
+}
+
+/// Timeout of the map exceeding the host-wide request timeout, see [`HostConfig::timeout_warnings`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeoutWarning<'a, K> {
+    /// Key of the timeout, None for the default one.
+    pub spec: Option<&'a K>,
+    pub timeout: Duration,
+    pub ceiling: Duration,
+}
+
+impl<K: Debug> Display for TimeoutWarning<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.spec {
+            Some(spec) => write!(f, "timeout of {:?}", spec)?,
+            None => write!(f, "default timeout")?,
+        }
+        write!(
+            f,
+            " {} exceeds request timeout {} and is cut down to it",
+            format_duration(self.timeout),
+            format_duration(self.ceiling)
+        )
+    }
+}
+
+/// Default timeouts keys per request method (like `POST = "write"`) or per method class.
+/// Exact method entries take precedence over the class ones.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MethodTimeouts<K: Eq + Hash + Default> {
+    /// Key for the safe methods (GET, HEAD, OPTIONS and TRACE).
+    #[serde(default)]
+    pub safe: Option<K>,
+    /// Key for all the other methods.
+    #[serde(default, rename = "unsafe")]
+    pub non_safe: Option<K>,
+    /// Keys for exact methods, named in upper case.
+    #[serde(flatten)]
+    pub methods: HashMap<String, K>,
+}
+
+impl<K: Eq + Hash + Default> Default for MethodTimeouts<K> {
+    fn default() -> Self {
+        Self {
+            safe: None,
+            non_safe: None,
+            methods: HashMap::default(),
+        }
+    }
+}
+
 /// Different parameters, being passed right into related reqwest's ClientBuilder methods.
 #[derive(Debug, Deserialize, Clone)]
 pub struct ExtraSettings {
@@ -39,6 +292,12 @@ pub struct ExtraSettings {
     /// Default is None, which means no timeout.
     #[serde(default)]
     pub connect_timeout: Option<Duration>,
+    /// A timeout for the whole request (from connecting until the response body is read),
+    /// acting as a ceiling for the timeouts map: each request gets the minimum of the two.
+    /// Streamed responses (see `Host::get_stream`) get it per chunk.
+    /// Default is None, which means only the timeouts map is used.
+    #[serde(default, with = "humantime_serde")]
+    pub request_timeout: Option<Duration>,
     /// Turns on/off verbouse connection logs (emitted with TRACE level for read and write operations on connections).
     /// Default is false.
     #[serde(default = "ExtraSettings::def_connection_verbose")]
@@ -59,6 +318,130 @@ pub struct ExtraSettings {
     /// Default is true.
     #[serde(default = "ExtraSettings::def_tcp_nodelay")]
     pub tcp_nodelay: bool,
+    /// Address family to connect with, for dual-stack environments stalling on one of them.
+    /// Default is None, which means both are tried (the backend prefers the first resolved address
+    /// family, falling back to the other one after a short delay).
+    #[serde(default)]
+    pub ip_family: Option<IpFamily>,
+    /// Minimal TLS version allowed (note that rustls backends support only 1.2 and 1.3).
+    /// Default is None, which means the backend default.
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[serde(default)]
+    pub min_tls_version: Option<TlsVersion>,
+    /// Turns off hostname verification (native-tls backend only), use with care.
+    /// Default is false.
+    #[cfg(feature = "tls-native")]
+    #[serde(default)]
+    pub danger_accept_invalid_hostnames: bool,
+    /// Base64 SHA-256 digests of the certificate SubjectPublicKeyInfo (like `pin-sha256` of HPKP),
+    /// any of which the host end-entity certificate must have on top of the CA trust (rustls backends only),
+    /// failing the connections with [`SpkiPinMismatch`](super::SpkiPinMismatch) otherwise.
+    /// Not applied to the client given to the host ready-made. Default is empty, which means no pinning.
+    #[cfg(feature = "tls-pinning")]
+    #[serde(default)]
+    pub pinned_spki_sha256: Vec<String>,
+    /// Settings unknown to the crate (e.g. backend-specific options), ignored by the default
+    /// client factory, to be used by a custom [`ClientFactory`](super::ClientFactory).
+    #[serde(flatten)]
+    pub other: HashMap<String, serde_json::Value>,
+}
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls1_0,
+    #[serde(rename = "1.1")]
+    Tls1_1,
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(src: TlsVersion) -> Self {
+        match src {
+            TlsVersion::Tls1_0 => Self::TLS_1_0,
+            TlsVersion::Tls1_1 => Self::TLS_1_1,
+            TlsVersion::Tls1_2 => Self::TLS_1_2,
+            TlsVersion::Tls1_3 => Self::TLS_1_3,
+        }
+    }
+}
+
+/// Client certificate (along with the intermediate ones, if any) and its private key as PEM files,
+/// read anew every time the client is built.
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct IdentityConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+impl IdentityConfig {
+    /// Contents of the certificate and the key files.
+    pub fn read(&self) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let read = |path: &Path| {
+            fs::read(path).map_err(|source| Error::IdentityFile {
+                path: path.into(),
+                source,
+            })
+        };
+        Ok((read(&self.cert)?, read(&self.key)?))
+    }
+
+    /// Reads the identity files for the reqwest TLS backend.
+    pub fn load(&self) -> Result<reqwest::Identity, Error> {
+        let (cert, key) = self.read()?;
+        // NOTE: reqwest picks native-tls over rustls whenever both are enabled
+        #[cfg(feature = "tls-native")]
+        let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key);
+        #[cfg(not(feature = "tls-native"))]
+        let identity = reqwest::Identity::from_pem(&[&cert[..], b"\n", &key[..]].concat());
+        identity.map_err(Error::InvalidIdentity)
+    }
+}
+
+/// Address family of the connections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /// Unspecified local address of the family: binding to it makes the backend
+    /// skip the resolved addresses of the other family.
+    pub fn local_address(self) -> IpAddr {
+        match self {
+            Self::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            Self::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
 }
 
 impl ExtraSettings {
@@ -78,6 +461,23 @@ impl ExtraSettings {
         if let Some(timeout) = self.connect_timeout {
             builder = builder.connect_timeout(timeout);
         }
+        if let Some(family) = self.ip_family {
+            builder = builder.local_address(family.local_address());
+        }
+
+        #[cfg(any(
+            feature = "tls-native",
+            feature = "tls-rustls",
+            feature = "tls-rustls-webpki"
+        ))]
+        if let Some(version) = self.min_tls_version {
+            builder = builder.min_tls_version(version.into());
+        }
+
+        #[cfg(feature = "tls-native")]
+        {
+            builder = builder.danger_accept_invalid_hostnames(self.danger_accept_invalid_hostnames);
+        }
 
         builder
             .connection_verbose(self.connection_verbose)
@@ -92,11 +492,24 @@ impl Default for ExtraSettings {
     fn default() -> Self {
         Self {
             connect_timeout: Default::default(),
+            request_timeout: Default::default(),
             connection_verbose: Self::def_connection_verbose(),
             pool_idle_timeout: Default::default(),
             pool_max_idle_per_host: Self::def_pool_max_idle_per_host(),
             tcp_keepalive: Default::default(),
             tcp_nodelay: Self::def_tcp_nodelay(),
+            ip_family: Default::default(),
+            #[cfg(any(
+                feature = "tls-native",
+                feature = "tls-rustls",
+                feature = "tls-rustls-webpki"
+            ))]
+            min_tls_version: Default::default(),
+            #[cfg(feature = "tls-native")]
+            danger_accept_invalid_hostnames: Default::default(),
+            #[cfg(feature = "tls-pinning")]
+            pinned_spki_sha256: Default::default(),
+            other: Default::default(),
         }
     }
 }
@@ -0,0 +1,241 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
+
+use super::{Host, HostConfig, Params, RequestOptions};
+
+/// Call to make along with the expected outcome. The shape (if any) is a sample of the response
+/// JSON body: the body must have every field of it with a value of the same type (`null` allows
+/// any), and every item of an array must match the first item of the sample array.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContractCase {
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "ContractCase::def_method")]
+    pub method: Method,
+    pub path: String,
+    /// Expected response status.
+    #[serde(default = "ContractCase::def_status")]
+    pub status: u16,
+    /// JSON body of the request, if any.
+    #[serde(default)]
+    pub body: Option<Value>,
+    #[serde(default)]
+    pub shape: Option<Value>,
+}
+
+impl ContractCase {
+    pub fn def_method() -> Method {
+        Method::GET
+    }
+
+    pub fn def_status() -> u16 {
+        200
+    }
+}
+
+/// Host config along with the cases to check it with, e.g. loaded from a file
+/// by the `skelphore-contract` binary.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContractSuite<K: Eq + Hash + Default> {
+    pub host: HostConfig<K>,
+    pub cases: Vec<ContractCase>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct CaseReport {
+    #[serde_as(as = "DisplayFromStr")]
+    pub method: Method,
+    pub path: String,
+    pub expected: u16,
+    /// Status received, if any response was.
+    pub status: Option<u16>,
+    #[serde(with = "humantime_serde")]
+    pub elapsed: Duration,
+    /// What went wrong, empty if the case passed.
+    pub failures: Vec<String>,
+}
+
+impl CaseReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for CaseReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let verdict = if self.passed() { "PASS" } else { "FAIL" };
+        write!(f, "{} {} {}", verdict, self.method, self.path)?;
+        match self.status {
+            Some(status) => write!(f, " {}", status)?,
+            None => f.write_str(" -")?,
+        }
+        write!(f, " ({:?})", self.elapsed)?;
+        for failure in &self.failures {
+            write!(f, "; {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of every case, in the order they were given.
+#[derive(Clone, Debug, Serialize)]
+pub struct ContractReport {
+    pub cases: Vec<CaseReport>,
+}
+
+impl ContractReport {
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(CaseReport::passed)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &CaseReport> {
+        self.cases.iter().filter(|case| !case.passed())
+    }
+}
+
+impl Display for ContractReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for case in &self.cases {
+            writeln!(f, "{}", case)?;
+        }
+        let failed = self.failed().count();
+        write!(f, "{} passed, {} failed", self.cases.len() - failed, failed)
+    }
+}
+
+/// Makes the calls of the cases one by one (with `X-Request-Id` of `contract-<index>`)
+/// and checks their outcomes, so the environment can be validated before the rollout.
+pub async fn check_contract<P: Params>(host: &Host<P>, cases: &[ContractCase]) -> ContractReport {
+    let mut reports = Vec::with_capacity(cases.len());
+    for (index, case) in cases.iter().enumerate() {
+        reports.push(check_case(host, case, &format!("contract-{}", index)).await);
+    }
+    ContractReport { cases: reports }
+}
+
+async fn check_case<P: Params>(host: &Host<P>, case: &ContractCase, xri: &str) -> CaseReport {
+    let started = Instant::now();
+    let mut report = CaseReport {
+        method: case.method.clone(),
+        path: case.path.clone(),
+        expected: case.status,
+        status: None,
+        elapsed: Duration::ZERO,
+        failures: Vec::new(),
+    };
+    let options = RequestOptions::default();
+    let result = host
+        .0
+        .execute(
+            case.method.clone(),
+            &case.path,
+            None,
+            xri.into(),
+            &options,
+            |request| match &case.body {
+                Some(body) => request.json(body),
+                None => request,
+            },
+        )
+        .await;
+    let response = match result {
+        Ok(response) => response,
+        Err(error) => {
+            report.elapsed = started.elapsed();
+            report.failures.push(error.to_string());
+            return report;
+        }
+    };
+    let status = response.status().as_u16();
+    report.status = Some(status);
+    if status != case.status {
+        report
+            .failures
+            .push(format!("status is {} instead of {}", status, case.status));
+    }
+    if let Some(shape) = &case.shape {
+        match response.json::<Value>().await {
+            Ok(body) => mismatches(shape, &body, "$", &mut report.failures),
+            Err(error) => report.failures.push(format!("body is not JSON: {}", error)),
+        }
+    }
+    report.elapsed = started.elapsed();
+    report
+}
+
+fn mismatches(shape: &Value, value: &Value, at: &str, found: &mut Vec<String>) {
+    match (shape, value) {
+        (Value::Null, _) => {}
+        (Value::Object(fields), Value::Object(values)) => {
+            for (name, field) in fields {
+                let at = format!("{}.{}", at, name);
+                match values.get(name) {
+                    Some(value) => mismatches(field, value, &at, found),
+                    None => found.push(format!("{} is missing", at)),
+                }
+            }
+        }
+        (Value::Array(items), Value::Array(values)) => {
+            if let Some(item) = items.first() {
+                for (index, value) in values.iter().enumerate() {
+                    mismatches(item, value, &format!("{}[{}]", at, index), found);
+                }
+            }
+        }
+        (shape, value) if type_name(shape) == type_name(value) => {}
+        (shape, value) => found.push(format!(
+            "{} is {} instead of {}",
+            at,
+            type_name(value),
+            type_name(shape)
+        )),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn shapes_are_matched() {
+        let shape = json!({ "id": 0, "tags": [""], "owner": { "name": "" }, "extra": null });
+        let mut found = Vec::new();
+        mismatches(
+            &shape,
+            &json!({ "id": 7, "tags": ["a", 1], "owner": {}, "extra": [] , "more": true }),
+            "$",
+            &mut found,
+        );
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                "$.owner.name is missing".to_owned(),
+                "$.tags[1] is number instead of string".to_owned(),
+            ]
+        );
+    }
+}
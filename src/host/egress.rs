@@ -0,0 +1,52 @@
+use reqwest::Method;
+use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+use super::Error;
+
+/// Allowlist of the methods and paths the host may be requested with, anything else is rejected
+/// with [`Error::PolicyDenied`]. Absent lists mean no restriction. The policy is checked by the
+/// fallible builders (like [`Host::try_request`](super::Host::try_request)) and the sending methods
+/// (like [`Host::send`](super::Host::send)), but not by the infallible builders (like
+/// [`Host::get`](super::Host::get)), since the requests they build are sent by reqwest directly.
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EgressPolicy {
+    #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
+    #[serde(default)]
+    pub methods: Option<Vec<Method>>,
+    /// Path prefixes, matched by whole segments (so "/api" allows "/api/v1", but not "/apis").
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+}
+
+impl EgressPolicy {
+    pub fn check(&self, method: &Method, path: &str) -> Result<(), Error> {
+        let method_allowed = self
+            .methods
+            .as_ref()
+            .is_none_or(|methods| methods.contains(method));
+        let path_allowed = self.paths.as_ref().is_none_or(|prefixes| {
+            // NOTE: dot segments are refused, otherwise they could escape the allowed prefix
+            !path
+                .split('/')
+                .any(|segment| segment == "." || segment == "..")
+                && prefixes.iter().any(|prefix| has_prefix(path, prefix))
+        });
+        if method_allowed && path_allowed {
+            Ok(())
+        } else {
+            Err(Error::PolicyDenied {
+                method: method.clone(),
+                path: path.into(),
+            })
+        }
+    }
+}
+
+fn has_prefix(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        None => false,
+        Some(rest) => prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+    }
+}
@@ -0,0 +1,57 @@
+use std::{convert::Infallible, fmt::Display};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Wrapper of the payload in API answers, which is being opened by typed send helpers.
+pub trait Envelope<T>: DeserializeOwned {
+    type Fail: Display;
+
+    fn open(self) -> Result<T, Self::Fail>;
+}
+
+/// No envelope at all: the answer is the payload itself.
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct Bare<T>(pub T);
+
+impl<T: DeserializeOwned> Envelope<T> for Bare<T> {
+    type Fail = Infallible;
+
+    fn open(self) -> Result<T, Self::Fail> {
+        Ok(self.0)
+    }
+}
+
+/// Widely used `{ "status": ..., "data": ..., "error": ... }` envelope.
+#[derive(Deserialize)]
+pub struct DataEnvelope<T> {
+    pub status: Option<serde_json::Value>,
+    pub data: Option<T>,
+    pub error: Option<serde_json::Value>,
+}
+
+impl<T: DeserializeOwned> Envelope<T> for DataEnvelope<T> {
+    type Fail = DataEnvelopeFail;
+
+    fn open(self) -> Result<T, Self::Fail> {
+        match (self.error, self.data) {
+            (Some(error), _) if !error.is_null() => Err(DataEnvelopeFail::Error {
+                status: self.status,
+                error,
+            }),
+            (_, Some(data)) => Ok(data),
+            (_, None) => Err(DataEnvelopeFail::NoData(self.status)),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DataEnvelopeFail {
+    #[error("Answer with status {status:?} contains error {error}")]
+    Error {
+        status: Option<serde_json::Value>,
+        error: serde_json::Value,
+    },
+    #[error("Answer with status {0:?} contains neither data nor error")]
+    NoData(Option<serde_json::Value>),
+}
@@ -0,0 +1,69 @@
+use std::{sync::mpsc::Sender, time::Duration};
+
+use reqwest::{Method, StatusCode};
+
+use super::Labels;
+
+#[cfg(feature = "pinger")]
+use crate::ping::Health;
+
+pub type EventSender = Sender<HostEvent>;
+
+/// Structured notification about what is going on with the host, see [`Host::with_events`](super::Host::with_events).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostEvent {
+    /// Request is built (but not necessarily sent yet).
+    RequestBuilt {
+        method: Method,
+        path: String,
+        xri: Option<String>,
+        labels: Labels,
+    },
+    /// Response head is received for the request sent through the `send` family of methods.
+    Response {
+        method: Method,
+        path: String,
+        xri: String,
+        status: StatusCode,
+        elapsed: Duration,
+        labels: Labels,
+    },
+    /// Request sent through the `send` family of methods failed without any response.
+    Error {
+        method: Method,
+        path: String,
+        xri: String,
+        error: String,
+        labels: Labels,
+    },
+    #[cfg(feature = "pinger")]
+    PingSucceeded { latency: Duration },
+    #[cfg(feature = "pinger")]
+    PingFailed {
+        error: String,
+        consecutive: u32,
+        xri: String,
+    },
+    #[cfg(feature = "pinger")]
+    HealthChanged { old: Health, new: Health },
+    /// Ping took longer than its period, so the next one is made right after it.
+    #[cfg(feature = "pinger")]
+    PingOverran { elapsed: Duration, period: Duration },
+    /// Credentials are replaced with [`Host::set_credentials`](super::Host::set_credentials).
+    CredentialsReloaded,
+    /// Client is rebuilt with the identity reloaded, see [`Host::reload_identity`](super::Host::reload_identity).
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    IdentityReloaded,
+}
+
+/// Sends the event if there is a receiver, never blocking: events are dropped once
+/// the receiver is gone.
+pub(crate) fn emit(events: &Option<EventSender>, event: HostEvent) {
+    if let Some(events) = events {
+        let _ = events.send(event);
+    }
+}
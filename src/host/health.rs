@@ -0,0 +1,100 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::Serialize;
+
+#[cfg(feature = "tls-expiry")]
+use super::CertificateInfo;
+use super::ClockSkew;
+#[cfg(feature = "pinger")]
+use crate::ping::PingStatus;
+use crate::Scheme;
+
+/// Host state summary, suitable to be returned right from readiness endpoints.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// Host address (host and port).
+    pub address: String,
+    pub scheme: Scheme,
+    #[cfg(feature = "pinger")]
+    pub ping: PingReport,
+    /// Number of requests sent but not yet answered.
+    pub inflight: usize,
+    /// Host TLS certificate seen last, if monitored with
+    /// [`HostConfig::tls_expiry`](super::HostConfig::tls_expiry).
+    #[cfg(feature = "tls-expiry")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<CertificateInfo>,
+}
+
+/// Observations made on the host traffic, see [`Host::runtime_info`](super::Host::runtime_info).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RuntimeInfo {
+    /// Host clock skew estimated from the last response, if enabled with
+    /// [`HostConfig::clock_skew`](super::HostConfig::clock_skew) and any Date header is received.
+    pub clock_skew: Option<ClockSkew>,
+}
+
+#[cfg(feature = "pinger")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum PingReport {
+    /// No ping is configured for the host.
+    Disabled,
+    /// Ping is configured, but the pinger is not spawned.
+    Configured,
+    Running(PingStatus),
+}
+
+/// Counter of the requests being sent at the moment.
+#[derive(Clone, Default)]
+pub(crate) struct InFlight(Arc<Counter>);
+
+#[derive(Default)]
+pub(crate) struct Counter {
+    count: AtomicUsize,
+    /// Notified every time the count drops to zero.
+    #[cfg(feature = "tokio")]
+    idle: tokio::sync::Notify,
+}
+
+impl InFlight {
+    // NOTE: sequentially consistent, so that the drain either sees the request entered
+    // or the request sees the host draining
+    pub(crate) fn enter(&self) -> InFlightGuard {
+        self.0.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.0.clone())
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.0.count.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no requests are in flight.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn idle(&self) {
+        loop {
+            // NOTE: the waiter is registered before the check, so the wakeup can't be missed
+            let idle = self.0.idle.notified();
+            if self.count() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+pub(crate) struct InFlightGuard(Arc<Counter>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        #[allow(unused_variables)]
+        let previous = self.0.count.fetch_sub(1, Ordering::SeqCst);
+        #[cfg(feature = "tokio")]
+        if previous == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+use reqwest::{
+    header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION},
+    Method, Request, RequestBuilder, Url,
+};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+use crate::secret::Secret;
+
+/// Inspectable copy of the request built by the host (see [`HostConfig::inspect_requests`](super::HostConfig::inspect_requests)),
+/// since [`RequestBuilder`] itself tells nothing. Sensitive header values (like credentials) are
+/// replaced with `***`. Client default headers (like User-Agent) are added later, so they are not here.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RequestSpec {
+    #[serde_as(as = "DisplayFromStr")]
+    pub method: Method,
+    #[serde_as(as = "DisplayFromStr")]
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+}
+
+impl RequestSpec {
+    /// Gives None if the builder can't be copied or built.
+    pub(crate) fn of(builder: &RequestBuilder) -> Option<Self> {
+        // NOTE: the copy is built since the builder gives no access to what it has
+        let request = builder.try_clone()?.build().ok()?;
+        Some(Self::from(&request))
+    }
+
+    /// First value of the header (names are case-insensitive), if any.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<&Request> for RequestSpec {
+    fn from(request: &Request) -> Self {
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let secret = value.is_sensitive()
+                    || [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE].contains(name);
+                let value = if secret {
+                    Secret::<String>::REDACTED.to_owned()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.as_str().to_owned(), value)
+            })
+            .collect();
+        Self {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers,
+            timeout: request.timeout().copied(),
+        }
+    }
+}
+
+/// Ring buffer of the latest request specs.
+pub(crate) struct RequestLog {
+    capacity: usize,
+    specs: Mutex<VecDeque<RequestSpec>>,
+}
+
+impl RequestLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            specs: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub(crate) fn record(&self, spec: RequestSpec) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut specs = self
+            .specs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if specs.len() == self.capacity {
+            specs.pop_front();
+        }
+        specs.push_back(spec);
+    }
+
+    pub(crate) fn specs(&self) -> Vec<RequestSpec> {
+        self.specs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+use bytes::Bytes;
+use reqwest::{RequestBuilder, Response};
+
+use super::FullResponse;
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
+pub enum InteropError {
+    #[error("Failed building request: {0}")]
+    Build(#[source] reqwest::Error),
+    #[error("Failed converting request: {0}")]
+    Convert(#[source] http::Error),
+    #[error("Request body is a stream, so it can't be exported")]
+    StreamedBody,
+    #[error("Failed receiving response body: {0}")]
+    Body(#[source] reqwest::Error),
+}
+
+/// Exports the prepared request (e.g. the one from [`Host::request`](super::Host::request))
+/// to be passed to `http`-based middleware. Note that the timeout is not kept, as `http::Request` has no place for it.
+pub fn export_request(request: RequestBuilder) -> Result<http::Request<Bytes>, InteropError> {
+    let request = request.build().map_err(InteropError::Build)?;
+    let body = match request.body() {
+        None => Bytes::new(),
+        Some(body) => Bytes::copy_from_slice(body.as_bytes().ok_or(InteropError::StreamedBody)?),
+    };
+    let mut exported = http::Request::builder()
+        .method(request.method().clone())
+        .uri(request.url().as_str())
+        .version(request.version())
+        .body(body)
+        .map_err(InteropError::Convert)?;
+    *exported.headers_mut() = request.headers().clone();
+    Ok(exported)
+}
+
+/// Receives the response body to give the response as the `http` one.
+pub async fn import_response(response: Response) -> Result<http::Response<Bytes>, InteropError> {
+    FullResponse::receive(response)
+        .await
+        .map(Into::into)
+        .map_err(InteropError::Body)
+}
+
+impl From<FullResponse> for http::Response<Bytes> {
+    fn from(src: FullResponse) -> Self {
+        let FullResponse {
+            status,
+            headers,
+            body,
+        } = src;
+        let mut response = http::Response::new(body);
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+    }
+}
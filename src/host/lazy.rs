@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+
+use super::{Error, Host, HostConfig, Params, TimeoutsParams};
+#[cfg(feature = "pinger")]
+use crate::ping::Behaviour;
+
+type Key<P> = <<P as Params>::Timeouts as TimeoutsParams>::Key;
+type Builder<P> = Box<dyn Fn(HostConfig<Key<P>>) -> Result<Host<P>, Error> + Send + Sync>;
+
+/// Host which is created on the first use (or [`LazyHost::connect`]) rather than right away,
+/// so a temporarily invalid config fails only the code actually using the host.
+/// Failed creation is repeated on the next use.
+pub struct LazyHost<P: Params> {
+    config: HostConfig<Key<P>>,
+    builder: Builder<P>,
+    host: Mutex<Option<Host<P>>>,
+}
+
+impl<P: Params + 'static> LazyHost<P>
+where
+    Key<P>: Clone,
+{
+    #[cfg(feature = "pinger")]
+    pub fn new<B: Behaviour<Handling = P::Handling>>(config: HostConfig<Key<P>>) -> Self
+    where
+        B::ProcessError: Default,
+    {
+        Self::with_builder(config, Host::new::<B>)
+    }
+
+    #[cfg(not(feature = "pinger"))]
+    pub fn new(config: HostConfig<Key<P>>) -> Self {
+        Self::with_builder(config, Host::new)
+    }
+
+    /// Same as [`LazyHost::new`], but creates the host with the given function
+    /// (e.g. [`Host::with_ping_handler`] with some handler).
+    pub fn with_builder<F>(config: HostConfig<Key<P>>, builder: F) -> Self
+    where
+        F: Fn(HostConfig<Key<P>>) -> Result<Host<P>, Error> + Send + Sync + 'static,
+    {
+        Self {
+            config,
+            builder: Box::new(builder),
+            host: Mutex::default(),
+        }
+    }
+
+    /// Gives the host, creating it if not yet.
+    pub fn connect(&self) -> Result<Host<P>, Error> {
+        let mut host = self
+            .host
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(host) = &*host {
+            return Ok(host.clone());
+        }
+        let created = (self.builder)(self.config.clone())?;
+        *host = Some(created.clone());
+        Ok(created)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.host
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .is_some()
+    }
+}
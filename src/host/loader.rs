@@ -0,0 +1,338 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+use super::HostConfig;
+
+/// Failure of loading [`HostConfig`] from a file. Parsing failures are told along with the place
+/// in the file and the path of the field (like `timeouts.default: invalid value ...`) when known.
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because std::io::Error doesn't implement it
+pub enum ConfigFileError {
+    #[error("Failed reading config file '{}': {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Failed parsing config file '{}'{}: {message}", path.display(), position(*line, *column))]
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+}
+
+fn position(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(" at line {}, column {}", line, column),
+        (Some(line), None) => format!(" at line {}", line),
+        _ => String::new(),
+    }
+}
+
+/// Failure of parsing the text, with no file path yet.
+struct ParseFailure {
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+}
+
+impl<K: Eq + std::hash::Hash + Default> HostConfig<K>
+where
+    Self: DeserializeOwned,
+{
+    /// Loads the config from the TOML file.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file<T: AsRef<Path>>(path: T) -> Result<Self, ConfigFileError> {
+        load(path.as_ref(), parse_toml)
+    }
+
+    /// Loads the config from the YAML file.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_file<T: AsRef<Path>>(path: T) -> Result<Self, ConfigFileError> {
+        load(path.as_ref(), parse_yaml)
+    }
+
+    /// Loads the config from the JSON file.
+    pub fn from_json_file<T: AsRef<Path>>(path: T) -> Result<Self, ConfigFileError> {
+        load(path.as_ref(), parse_json)
+    }
+}
+
+fn load<T, F>(path: &Path, parse: F) -> Result<T, ConfigFileError>
+where
+    F: FnOnce(&str) -> Result<T, ParseFailure>,
+{
+    let text = fs::read_to_string(path).map_err(|source| ConfigFileError::Read {
+        path: path.into(),
+        source,
+    })?;
+    parse(&text).map_err(|failure| ConfigFileError::Parse {
+        path: path.into(),
+        line: failure.line,
+        column: failure.column,
+        message: failure.message,
+    })
+}
+
+/// Removes the location suffix the message is given with by `serde_json` and `serde_yaml`,
+/// since the location is told separately.
+fn cut_location(message: &mut String, line: usize, column: usize) {
+    let suffix = format!(" at line {} column {}", line, column);
+    if message.ends_with(&suffix) {
+        message.truncate(message.len() - suffix.len());
+    }
+}
+
+/// Message prefixed with the field path, if any (the way `serde_yaml` does it). The message
+/// prefixed already (by `serde_yaml` itself) is kept as is.
+fn at_field(field: Option<String>, message: &str) -> String {
+    match field {
+        Some(field) if !field.is_empty() && !message.starts_with(&format!("{}: ", field)) => {
+            format!("{}: {}", field, message)
+        }
+        _ => message.to_owned(),
+    }
+}
+
+#[cfg(feature = "toml")]
+fn parse_toml<T: DeserializeOwned>(text: &str) -> Result<T, ParseFailure> {
+    toml::from_str(text).map_err(|error| {
+        let start = error.span().map(|span| span.start);
+        let (line, column) = match start {
+            Some(offset) => {
+                let before = &text[..offset.min(text.len())];
+                let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+                (
+                    Some(before.matches('\n').count() + 1),
+                    Some(before[line_start..].chars().count() + 1),
+                )
+            }
+            None => (None, None),
+        };
+        let field = start.and_then(|offset| toml_field_at(text, offset));
+        ParseFailure {
+            line,
+            column,
+            message: at_field(field, error.message()),
+        }
+    })
+}
+
+/// Dotted path of the innermost TOML item at the offset, if the text is valid TOML at all.
+#[cfg(feature = "toml")]
+fn toml_field_at(text: &str, offset: usize) -> Option<String> {
+    use toml_edit::{ImDocument, Item, Table, Value};
+
+    fn covers(span: Option<std::ops::Range<usize>>, offset: usize) -> bool {
+        span.is_some_and(|span| span.contains(&offset))
+    }
+
+    fn in_value(value: &Value, offset: usize, path: &mut Vec<String>) -> bool {
+        let found = match value {
+            Value::Array(array) => array.iter().enumerate().any(|(index, value)| {
+                path.push(format!("[{}]", index));
+                in_value(value, offset, path) || {
+                    path.pop();
+                    false
+                }
+            }),
+            Value::InlineTable(table) => table.iter().any(|(key, value)| {
+                path.push(key.to_owned());
+                in_value(value, offset, path) || {
+                    path.pop();
+                    false
+                }
+            }),
+            _ => false,
+        };
+        found || covers(value.span(), offset)
+    }
+
+    fn in_item(item: &Item, offset: usize, path: &mut Vec<String>) -> bool {
+        match item {
+            Item::None => false,
+            Item::Value(value) => in_value(value, offset, path),
+            Item::Table(table) => in_table(table, offset, path),
+            Item::ArrayOfTables(tables) => tables.iter().enumerate().any(|(index, table)| {
+                path.push(format!("[{}]", index));
+                in_table(table, offset, path) || {
+                    path.pop();
+                    false
+                }
+            }),
+        }
+    }
+
+    fn in_table(table: &Table, offset: usize, path: &mut Vec<String>) -> bool {
+        let found = table.iter().any(|(key, item)| {
+            path.push(key.to_owned());
+            let key_covers = table.key(key).is_some_and(|key| covers(key.span(), offset));
+            in_item(item, offset, path) || key_covers || {
+                path.pop();
+                false
+            }
+        });
+        found || covers(table.span(), offset)
+    }
+
+    let document = ImDocument::parse(text).ok()?;
+    let mut path = Vec::new();
+    if !in_table(document.as_table(), offset, &mut path) {
+        return None;
+    }
+    Some(join_path(path))
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml<T: DeserializeOwned>(text: &str) -> Result<T, ParseFailure> {
+    serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(text)).map_err(|error| {
+        let field = field_of(error.path());
+        let error = error.into_inner();
+        let location = error.location();
+        let mut message = error.to_string();
+        if let Some(location) = &location {
+            cut_location(&mut message, location.line(), location.column());
+        }
+        ParseFailure {
+            line: location.as_ref().map(|location| location.line()),
+            column: location.as_ref().map(|location| location.column()),
+            message: at_field(field, &message),
+        }
+    })
+}
+
+fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, ParseFailure> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| (field_of(error.path()), error.into_inner()))
+        .and_then(|config| {
+            // NOTE: trailing characters are refused the way serde_json::from_str does it
+            deserializer.end().map_err(|error| (None, error))?;
+            Ok(config)
+        })
+        .map_err(|(field, error)| {
+            let mut message = error.to_string();
+            if error.line() == 0 {
+                return ParseFailure {
+                    line: None,
+                    column: None,
+                    message: at_field(field, &message),
+                };
+            }
+            cut_location(&mut message, error.line(), error.column());
+            ParseFailure {
+                line: Some(error.line()),
+                column: Some(error.column()),
+                message: at_field(field, &message),
+            }
+        })
+}
+
+/// Path of the field the failure is told for by `serde_path_to_error`, if it is not the root.
+fn field_of(path: &serde_path_to_error::Path) -> Option<String> {
+    path.iter().next().map(|_| path.to_string())
+}
+
+/// Joins the path segments with dots, except for the array indices (like `probes[0].path`).
+#[cfg(feature = "toml")]
+fn join_path(path: Vec<String>) -> String {
+    let mut joined = String::new();
+    for segment in path {
+        if !joined.is_empty() && !segment.starts_with('[') {
+            joined.push('.');
+        }
+        joined.push_str(&segment);
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::timeoutsmap::TrivialKey;
+
+    #[test]
+    fn json_failures_tell_field() {
+        let failure = parse_json::<HostConfig<TrivialKey>>(
+            "{\"target\": \"localhost:8080\",\n \"timeouts\": {\"default\": \"5 parsecs\"}}",
+        )
+        .err()
+        .expect("Config should not be parsed");
+
+        assert_eq!(failure.line, Some(2));
+        assert_eq!(
+            failure.message,
+            "timeouts.default: invalid value: string \"5 parsecs\", expected a duration"
+        );
+
+        let failure = parse_json::<HostConfig<TrivialKey>>(
+            r#"{"target": "localhost:8080", "correlation_headers": ["x-request-id", 5]}"#,
+        )
+        .err()
+        .expect("Config should not be parsed");
+
+        assert!(failure.message.starts_with("correlation_headers[1]: "));
+
+        let failure = parse_json::<HostConfig<TrivialKey>>(
+            r#"{"target": "localhost:8080", "profiles": {"a\"b": {"x": 1}}}"#,
+        )
+        .err()
+        .expect("Config should not be parsed");
+
+        assert!(failure.message.starts_with("profiles.a\"b.x: "));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_failures_tell_field() {
+        let failure = parse_toml::<HostConfig<TrivialKey>>(
+            "target = \"localhost:8080\"\n[timeouts]\ndefault = \"5 parsecs\"\n",
+        )
+        .err()
+        .expect("Config should not be parsed");
+
+        assert_eq!((failure.line, failure.column), (Some(3), Some(11)));
+        assert_eq!(
+            failure.message,
+            "timeouts.default: invalid value: string \"5 parsecs\", expected a duration"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_failures_tell_field() {
+        let failure = parse_yaml::<HostConfig<TrivialKey>>(
+            "target: localhost:8080\ntimeouts:\n  default: 5 parsecs\n",
+        )
+        .err()
+        .expect("Config should not be parsed");
+
+        assert_eq!(failure.line, Some(3));
+        assert_eq!(
+            failure.message,
+            "timeouts.default: invalid value: string \"5 parsecs\", expected a duration"
+        );
+    }
+
+    #[test]
+    fn config_file_is_loaded() {
+        let path = std::env::temp_dir().join(format!("skelphore-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{"target": "localhost:8080", "timeouts": {"default": "150ms"}}"#,
+        )
+        .expect("Config file should be written");
+        let config = HostConfig::<TrivialKey>::from_json_file(&path);
+        let _ = fs::remove_file(&path);
+        let config = config.expect("Config should be loaded");
+
+        assert_eq!(config.timeouts.default, Duration::from_millis(150));
+        assert!(matches!(
+            HostConfig::<TrivialKey>::from_json_file(&path),
+            Err(ConfigFileError::Read { .. })
+        ));
+    }
+}
@@ -1,52 +1,184 @@
+#[cfg(feature = "audit")]
+pub mod audit;
+mod auth;
+#[cfg(feature = "cache")]
+pub mod cache;
 #[cfg(feature = "callbacks")]
 pub mod callbacks;
+#[cfg(feature = "tls-expiry")]
+pub mod certificate;
+pub mod client;
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "gzip")]
+pub mod compression;
 pub mod config;
+#[cfg(feature = "contract")]
+pub mod contract;
+pub mod egress;
+pub mod envelope;
+#[cfg(feature = "events")]
+pub mod events;
+pub mod health;
+pub mod inspect;
+#[cfg(feature = "http")]
+pub mod interop;
+pub mod lazy;
+pub mod loader;
+pub mod options;
+pub mod paths;
+#[cfg(feature = "tls-pinning")]
+pub mod pinning;
+pub mod registry;
+pub mod send;
+#[cfg(feature = "tokio")]
+pub mod shutdown;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "dns-timings")]
+mod timings;
+pub mod transport;
+pub mod view;
+#[cfg(feature = "tokio")]
+pub mod watcher;
 
 use std::{
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     fmt::{Debug, Formatter, Result as FmtResult},
     str::FromStr,
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
+#[cfg(feature = "tokio")]
+use std::{collections::VecDeque, mem};
 
+use arc_swap::ArcSwap;
+use arc_swap::ArcSwapOption;
+#[cfg(feature = "tokio")]
+use bytes::Bytes;
 use const_format::formatcp;
+#[cfg(feature = "tokio")]
+use futures::Stream;
+use futures::{
+    channel::oneshot,
+    future::{self, BoxFuture, FutureExt, Shared},
+    stream::{self, StreamExt},
+};
 pub use reqwest;
-use reqwest::{Client, Method, RequestBuilder, Url};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_TYPE, IF_MATCH},
+    Client, Method, Request, RequestBuilder, Response, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     address::Address,
-    credentials,
+    credentials::{self, Credentials},
     timeoutsmap::{
-        Params as TimeoutsParams, TimeoutsMap, TrivialKey, TrivialParams as TrivialTimeoutsParams,
+        Params as TimeoutsParams, Policy, TimeoutsMap, TrivialKey,
+        TrivialParams as TrivialTimeoutsParams,
     },
     Scheme,
 };
 
 #[cfg(feature = "pinger")]
-use crate::ping::{self, pinger, Behaviour, Handling, MinimalBehaviour, NoHandling};
+use crate::ping::{
+    self, keeper, pinger, Behaviour, Handling, Health, MinimalBehaviour, NoHandling, PingStatus,
+};
 
+#[cfg(feature = "audit")]
+pub use self::audit::*;
+#[cfg(feature = "audit")]
+use self::audit::{AuditSlot, PendingRecord};
+use self::auth::Auth;
+pub use self::client::*;
+use self::clock::ClockWatch;
+pub use self::clock::{ClockSkew, ClockSkewConfig};
+pub use self::codec::*;
 pub use self::config::*;
+pub use self::egress::*;
+pub use self::envelope::*;
+pub use self::health::*;
+use self::health::{InFlight, InFlightGuard};
+use self::inspect::RequestLog;
+pub use self::inspect::RequestSpec;
+#[cfg(feature = "http")]
+pub use self::interop::*;
+pub use self::lazy::*;
+pub use self::loader::*;
+pub use self::options::*;
+pub use self::paths::PathId;
+use self::paths::PathTable;
+pub use self::registry::*;
+pub use self::send::*;
+use self::send::{request_origin, XriRef};
+#[cfg(feature = "dns-timings")]
+use self::timings::TimingResolver;
+pub use self::transport::*;
+pub use self::view::*;
 
+#[cfg(feature = "cache")]
+pub use self::cache::*;
 #[cfg(feature = "callbacks")]
 pub use self::callbacks::*;
+#[cfg(feature = "tls-expiry")]
+use self::certificate::CertificateWatch;
+#[cfg(feature = "tls-expiry")]
+pub use self::certificate::{CertificateExpiryConfig, CertificateInfo};
+#[cfg(feature = "gzip")]
+pub use self::compression::*;
+#[cfg(feature = "contract")]
+pub use self::contract::*;
+#[cfg(feature = "events")]
+use self::events::emit;
+#[cfg(feature = "events")]
+pub use self::events::*;
+#[cfg(feature = "tls-pinning")]
+pub use self::pinning::SpkiPinMismatch;
+#[cfg(feature = "tokio")]
+pub use self::shutdown::*;
+#[cfg(feature = "tokio")]
+pub use self::watcher::*;
 
 #[cfg(feature = "pinger")]
 #[derive(Debug)]
-pub enum PingState<H> {
-    Config(ping::Config),
+pub enum PingState<H, C = ping::Config> {
+    Config(C),
     Handle(H),
 }
 
+#[cfg(feature = "pinger")]
+impl<H, C> PingState<H, C> {
+    pub fn handle(&self) -> Option<&H> {
+        match self {
+            Self::Config(_) => None,
+            Self::Handle(handle) => Some(handle),
+        }
+    }
+}
+
 pub trait Params {
     type Timeouts: TimeoutsParams;
     #[cfg(feature = "pinger")]
     type Handling: Handling;
     #[cfg(feature = "callbacks")]
     type Callbacks: Callbacks;
+    type Envelope<T: DeserializeOwned>: Envelope<T>;
+    #[cfg(feature = "cache")]
+    type Cache: CacheStore + Default;
+    type Transport: Transport;
+    type ClientFactory: ClientFactory;
     const USER_AGENT: &'static str;
+
+    /// Classifies the failed request for the code repeating it, as upstreams differ
+    /// in conventions (e.g. some of them answer 409 to the requests worth repeating).
+    fn classify(error: &SendError) -> ErrorClass {
+        ErrorClass::of(error)
+    }
 }
 
 pub struct TrivialParams;
@@ -57,237 +189,2739 @@ impl Params for TrivialParams {
     type Handling = NoHandling;
     #[cfg(feature = "callbacks")]
     type Callbacks = TrivialCallbacks;
+    type Envelope<T: DeserializeOwned> = Bare<T>;
+    #[cfg(feature = "cache")]
+    type Cache = NoCache;
+    type Transport = Client;
+    type ClientFactory = DefaultClientFactory;
     const USER_AGENT: &'static str =
         formatcp!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 }
 
+#[cfg(feature = "tokio")]
+const MAX_BACKOFF_SHIFT: u32 = 5;
+
+/// Base headers of the requests made with the given credentials, see [`HostInner::base_headers`].
+struct AssembledHeaders {
+    credentials: Arc<HeaderMap>,
+    headers: HeaderMap,
+}
+
+type Landing = Result<FullResponse, Arc<SendError>>;
+/// Outcome of the request shared by several callers, given nothing if the caller dispatching
+/// the request (the pilot) is dropped before it completes.
+type Flight = Shared<BoxFuture<'static, Option<Landing>>>;
+
+fn take_off() -> (Flight, oneshot::Sender<Landing>) {
+    let (pilot, landing) = oneshot::channel();
+    (landing.map(Result::ok).boxed().shared(), pilot)
+}
+
+/// Whether the pilot of the flight was dropped, so another caller has to take its place.
+fn is_abandoned(flight: &Flight) -> bool {
+    matches!(flight.peek(), Some(None))
+}
+
+/// Whether the flight landed with a failure, so the request is to be retried rather than deduped.
+fn has_failed(flight: &Flight) -> bool {
+    // NOTE: polled rather than peeked, since nobody may be awaiting the flight but its pilot
+    matches!(flight.clone().now_or_never(), Some(Some(Err(_))))
+}
+
+/// Method, path and idempotency key of the deduped request.
+type DedupeEntry = (Method, String, String);
+
+/// Fetches the fresh credentials headers out of schedule, giving nothing if failed.
+#[cfg(feature = "tokio")]
+type Refresher = Box<dyn Fn() -> BoxFuture<'static, Option<HeaderMap>> + Send + Sync>;
+
+// NOTE: the state read by every request is either fixed since creation (like the base URL and
+// timeouts) or reloaded through `arc_swap` (like the credentials, the assembled headers and the
+// client), so building requests never waits for locks, even while the state is being reloaded
 struct HostInner<P: Params = TrivialParams> {
-    client: Client,
+    client: ArcSwap<Client>,
+    transport: ArcSwap<P::Transport>,
+    /// Config the client is rebuilt with once the identity is reloaded, if the host built it.
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    rebuild: Option<HostConfig<<P::Timeouts as TimeoutsParams>::Key>>,
     base_url: Url,
+    paths: PathTable,
+    scheme: Scheme,
+    credentials: Arc<ArcSwap<HeaderMap>>,
+    #[cfg(feature = "tokio")]
+    refresh_on_unauthorized: bool,
+    #[cfg(feature = "tokio")]
+    refresher: ArcSwapOption<Refresher>,
+    credential_sets: HashMap<String, HeaderMap>,
+    profiles: HashMap<String, HeaderMap>,
+    profile: HeaderMap,
+    assembled: ArcSwapOption<AssembledHeaders>,
+    correlation_headers: Vec<HeaderName>,
+    clock: Option<ClockWatch>,
+    #[cfg(feature = "tls-expiry")]
+    certificate: Option<CertificateWatch>,
+    request_log: Option<RequestLog>,
+    #[cfg(feature = "gzip")]
+    compression: Option<CompressionConfig>,
+    egress: Option<EgressPolicy>,
+    auth: Auth,
+    #[cfg(feature = "audit")]
+    audit: AuditSlot,
+    #[cfg(feature = "events")]
+    events: Option<EventSender>,
     timeouts: TimeoutsMap<P::Timeouts>,
+    method_timeouts: MethodDefaults,
+    request_timeout: Option<Duration>,
     #[cfg(feature = "pinger")]
     ping: Option<PingState<<P::Handling as Handling>::Handle>>,
+    #[cfg(feature = "pinger")]
+    keepalive: Option<PingState<<P::Handling as Handling>::Handle, ping::KeepaliveConfig>>,
+    #[cfg(feature = "pinger")]
+    ping_status: Arc<Mutex<PingStatus>>,
+    #[cfg(feature = "pinger")]
+    first_ping: Option<Shared<oneshot::Receiver<Health>>>,
+    /// Kept aside of the pinger state for the checks on demand.
+    #[cfg(feature = "pinger")]
+    ping_config: Option<ping::Config>,
+    #[cfg(feature = "cache")]
+    cache: Arc<P::Cache>,
+    #[cfg(feature = "cache")]
+    cache_config: CacheConfig,
+    /// Paths being revalidated in the background.
+    #[cfg(all(feature = "cache", feature = "tokio"))]
+    refreshing: Arc<Mutex<std::collections::HashSet<String>>>,
+    flights: Mutex<HashMap<String, Flight>>,
+    dedupe_window: Option<Duration>,
+    /// Requests sent with idempotency keys, by their method, path and key.
+    deduped: Mutex<HashMap<DedupeEntry, (Instant, Flight)>>,
+    inflight: InFlight,
+    draining: AtomicBool,
+    #[cfg(feature = "dns-timings")]
+    resolver: TimingResolver,
 }
 
-fn base_url(scheme: &'static str, instance: Address) -> Result<Url, Error> {
+pub(crate) fn base_url(scheme: &'static str, instance: Address) -> Result<Url, Error> {
     let candidate = format!("{}://{}", scheme, instance);
     Url::from_str(&candidate).map_err(|source| Error::UrlParse { candidate, source })
 }
 
+/// Rejects paths which would silently produce an unexpected URL if passed to [`Url::set_path`].
+fn validate_path(path: &str) -> Result<(), Error> {
+    let reason = if !path.starts_with('/') {
+        "must start with '/'"
+    } else if path.contains('?') {
+        "contains query string"
+    } else if path.contains('#') {
+        "contains fragment"
+    } else if path.contains('\\') {
+        "contains backslash"
+    } else if path.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        "contains whitespace or control characters"
+    } else {
+        return Ok(());
+    };
+    Err(Error::MalformedPath {
+        path: path.into(),
+        reason,
+    })
+}
+
+fn store_credentials(slot: &ArcSwap<HeaderMap>, credentials: Credentials) -> Result<(), Error> {
+    let credentials = credentials.try_into().map_err(Error::CredentialsConvert)?;
+    slot.store(Arc::new(credentials));
+    Ok(())
+}
+
+fn profile_headers(profile: &str, headers: HashMap<String, String>) -> Result<HeaderMap, Error> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let fail = || Error::InvalidProfileHeader {
+            profile: profile.into(),
+            header: name.clone(),
+        };
+        let header_name = HeaderName::from_str(&name).map_err(|_| fail())?;
+        let header_value = HeaderValue::from_str(&value).map_err(|_| fail())?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// Timeouts resolved from [`MethodTimeouts`] beforehand, so keys are not needed to be kept.
+#[derive(Default)]
+struct MethodDefaults {
+    methods: HashMap<Method, Duration>,
+    safe: Option<Duration>,
+    non_safe: Option<Duration>,
+}
+
+impl MethodDefaults {
+    fn new<P: TimeoutsParams>(
+        config: MethodTimeouts<P::Key>,
+        timeouts: &TimeoutsMap<P>,
+    ) -> Result<Self, Error> {
+        let methods = config
+            .methods
+            .into_iter()
+            .map(|(name, spec)| {
+                let method = Method::from_str(&name).map_err(|_| Error::InvalidMethod(name))?;
+                Ok((method, timeouts[&spec]))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            methods,
+            safe: config.safe.map(|spec| timeouts[&spec]),
+            non_safe: config.non_safe.map(|spec| timeouts[&spec]),
+        })
+    }
+
+    fn get(&self, method: &Method) -> Option<Duration> {
+        let safe = matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        );
+        self.methods
+            .get(method)
+            .copied()
+            .or(if safe { self.safe } else { self.non_safe })
+    }
+}
+
+fn set_scheme(url: &mut Url, scheme: Scheme) {
+    // NOTE: port is kept explicitly, otherwise the default port of the previous scheme would be lost
+    let port = url.port_or_known_default();
+    // NOTE: switching between special schemes (http, https, ws, wss) never fails
+    let _ = url.set_scheme(scheme.into());
+    let _ = url.set_port(port);
+}
+
 impl<P: Params> HostInner<P> {
     pub fn new(config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>) -> Result<Self, Error> {
+        Self::with_client(config, None)
+    }
+
+    /// Builds the client according to the config, both on creation and on the identity reloads.
+    fn build_client(
+        config: &HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        #[cfg(feature = "dns-timings")] resolver: &TimingResolver,
+    ) -> Result<Client, Error> {
+        #[allow(unused_mut)]
+        let mut builder = Client::builder().user_agent(P::USER_AGENT);
+        #[cfg(feature = "dns-timings")]
+        {
+            builder = builder.dns_resolver(Arc::new(resolver.clone()));
+        }
+        #[cfg(feature = "tls-expiry")]
+        if config.tls_expiry.is_some() {
+            builder = builder.tls_info(true);
+        }
+        // NOTE: the pinned TLS config has to carry the identity itself, reqwest ignores it otherwise
+        #[cfg(any(
+            feature = "tls-native",
+            feature = "tls-rustls",
+            feature = "tls-rustls-webpki"
+        ))]
+        #[allow(unused_mut)]
+        let mut identity = config.identity.as_ref();
+        #[cfg(feature = "tls-pinning")]
+        if let Some(extras) = config
+            .extras
+            .as_ref()
+            .filter(|extras| !extras.pinned_spki_sha256.is_empty())
+        {
+            builder = builder.use_preconfigured_tls(pinning::client_config(
+                &extras.pinned_spki_sha256,
+                extras.min_tls_version,
+                identity.take(),
+            )?);
+        }
+        #[cfg(any(
+            feature = "tls-native",
+            feature = "tls-rustls",
+            feature = "tls-rustls-webpki"
+        ))]
+        if let Some(identity) = identity {
+            builder = builder.identity(identity.load()?);
+        }
+        P::ClientFactory::build(builder, config)
+    }
+
+    /// Uses the given client (if any) instead of building one with the config.
+    fn with_client(
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        client: Option<Client>,
+    ) -> Result<Self, Error> {
+        let base_url = config.preview_base_url()?;
+
+        #[cfg(feature = "dns-timings")]
+        let resolver = TimingResolver::default();
+
+        // NOTE: the config is kept to rebuild the client with the identity reloaded, but not the secrets
+        #[cfg(any(
+            feature = "tls-native",
+            feature = "tls-rustls",
+            feature = "tls-rustls-webpki"
+        ))]
+        let rebuild = match (&client, &config.identity) {
+            (None, Some(_)) => Some(config.client_part()),
+            _ => None,
+        };
+        let client = match client {
+            Some(client) => client,
+            None => Self::build_client(
+                &config,
+                #[cfg(feature = "dns-timings")]
+                &resolver,
+            )?,
+        };
+        let request_timeout = config
+            .extras
+            .as_ref()
+            .and_then(|extras| extras.request_timeout);
+
         let HostConfig {
             credentials,
-            target,
+            #[cfg(feature = "tokio")]
+            refresh_on_unauthorized,
+            credential_sets,
+            profiles,
+            profile,
+            accept,
+            accept_encoding,
+            correlation_headers,
+            clock_skew,
+            #[cfg(feature = "tls-expiry")]
+            tls_expiry,
+            inspect_requests,
+            dedupe_window,
+            #[cfg(feature = "cache")]
+            cache,
+            #[cfg(feature = "gzip")]
+            compression,
+            target: _,
             scheme,
+            strict_scheme: _,
+            #[cfg(any(
+                feature = "tls-native",
+                feature = "tls-rustls",
+                feature = "tls-rustls-webpki"
+            ))]
+                identity: _,
+            egress,
             timeouts,
+            method_timeouts,
+            #[cfg(feature = "audit")]
+            audit_file,
             #[cfg(feature = "pinger")]
             ping,
-            extras,
+            #[cfg(feature = "pinger")]
+            keepalive,
+            #[cfg(feature = "digest")]
+            digest,
+            #[cfg(feature = "sigv4")]
+            sigv4,
+            extras: _,
         } = config;
 
-        let mut client = Client::builder().user_agent(P::USER_AGENT);
-
-        if let Some(cred_vals) = credentials {
-            client =
-                client.default_headers(cred_vals.try_into().map_err(Error::CredentialsConvert)?)
-        }
+        let timeouts = TimeoutsMap::<P::Timeouts>::from(timeouts);
+        let method_timeouts = MethodDefaults::new(method_timeouts, &timeouts)?;
 
-        if let Some(es) = extras {
-            client = es.apply(client);
+        #[cfg(feature = "pinger")]
+        if let Some(ping) = &ping {
+            if ping.timeout() > ping.period {
+                return Err(Error::PingTimeout {
+                    timeout: ping.timeout(),
+                    period: ping.period,
+                });
+            }
+            if ping.max_period() < ping.period {
+                return Err(Error::PingMaxPeriod {
+                    max_period: ping.max_period(),
+                    period: ping.period,
+                });
+            }
         }
 
-        let client = client
-            .https_only(matches!(scheme, Scheme::Https))
-            .build()
-            .map_err(Error::ClientBulid)?;
+        #[cfg(feature = "audit")]
+        let audit = match audit_file {
+            Some(path) => {
+                let sink =
+                    FileSink::open(&path).map_err(|source| Error::AuditFile { path, source })?;
+                AuditSlot::from_pointee(Box::new(sink) as Box<dyn AuditSink>)
+            }
+            None => AuditSlot::empty(),
+        };
 
-        let base_url = base_url(scheme.into(), target)?;
+        // NOTE: credentials are kept aside of the client default headers to be replaceable on the fly
+        let credentials = match credentials {
+            Some(cred_vals) => cred_vals.try_into().map_err(Error::CredentialsConvert)?,
+            None => HeaderMap::new(),
+        };
+        let credential_sets = credential_sets
+            .into_iter()
+            .map(|(name, cred_vals)| Ok((name, cred_vals.try_into()?)))
+            .collect::<Result<_, _>>()
+            .map_err(Error::CredentialsConvert)?;
+        let profiles = profiles
+            .into_iter()
+            .map(|(name, headers)| {
+                let headers = profile_headers(&name, headers)?;
+                Ok((name, headers))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        let mut defaults = HeaderMap::new();
+        for (name, value) in [(ACCEPT, accept), (ACCEPT_ENCODING, accept_encoding)] {
+            if let Some(value) = value {
+                let value =
+                    HeaderValue::from_str(&value).map_err(|_| Error::InvalidDefaultHeader {
+                        header: name.clone(),
+                        value,
+                    })?;
+                defaults.insert(name, value);
+            }
+        }
+        // NOTE: the profile headers take precedence over the default ones
+        let profile = match profile {
+            Some(name) => profiles
+                .get(&name)
+                .cloned()
+                .ok_or(Error::UnknownProfile(name))?,
+            None => HeaderMap::new(),
+        };
+        defaults.extend(profile);
+        let profile = defaults;
+        let correlation_headers = correlation_headers
+            .into_iter()
+            .map(|name| {
+                HeaderName::from_str(&name).map_err(|_| Error::InvalidCorrelationHeader(name))
+            })
+            .collect::<Result<_, _>>()?;
 
         Ok(Self {
-            client,
+            transport: ArcSwap::from_pointee(P::Transport::from_client(client.clone())),
+            client: ArcSwap::from_pointee(client),
+            #[cfg(any(
+                feature = "tls-native",
+                feature = "tls-rustls",
+                feature = "tls-rustls-webpki"
+            ))]
+            rebuild,
             base_url,
-            timeouts: TimeoutsMap::<P::Timeouts>::from(timeouts),
+            paths: PathTable::default(),
+            scheme,
+            credentials: Arc::new(ArcSwap::from_pointee(credentials)),
+            #[cfg(feature = "tokio")]
+            refresh_on_unauthorized,
+            #[cfg(feature = "tokio")]
+            refresher: ArcSwapOption::empty(),
+            credential_sets,
+            profiles,
+            profile,
+            assembled: ArcSwapOption::empty(),
+            correlation_headers,
+            clock: clock_skew.map(ClockWatch::new),
+            #[cfg(feature = "tls-expiry")]
+            certificate: tls_expiry.map(CertificateWatch::new),
+            request_log: inspect_requests.map(RequestLog::new),
+            #[cfg(feature = "gzip")]
+            compression,
+            egress,
+            #[cfg(feature = "audit")]
+            audit,
+            #[cfg(feature = "events")]
+            events: None,
+            auth: Auth {
+                #[cfg(feature = "digest")]
+                digest: digest.map(Arc::new),
+                #[cfg(feature = "sigv4")]
+                sigv4: sigv4.map(Arc::new),
+            },
+            timeouts,
+            method_timeouts,
+            request_timeout,
+            #[cfg(feature = "pinger")]
+            ping_config: ping.clone(),
             #[cfg(feature = "pinger")]
             ping: ping.map(PingState::Config),
+            #[cfg(feature = "pinger")]
+            keepalive: keepalive.map(PingState::Config),
+            #[cfg(feature = "pinger")]
+            ping_status: Arc::default(),
+            #[cfg(feature = "pinger")]
+            first_ping: None,
+            #[cfg(feature = "cache")]
+            cache: Arc::new(P::Cache::from_config(&cache)),
+            #[cfg(feature = "cache")]
+            cache_config: cache,
+            #[cfg(all(feature = "cache", feature = "tokio"))]
+            refreshing: Arc::default(),
+            flights: Mutex::default(),
+            dedupe_window,
+            deduped: Mutex::default(),
+            inflight: InFlight::default(),
+            draining: AtomicBool::new(false),
+            #[cfg(feature = "dns-timings")]
+            resolver,
         })
     }
 
-    fn url(&self, path: &str) -> Url {
+    fn url(&self, scheme: Option<Scheme>, path: &str) -> Url {
+        if scheme.is_none() {
+            if let Some(url) = self.paths.url(path) {
+                return url;
+            }
+        }
         let mut url = self.base_url.clone();
+        if let Some(scheme) = scheme {
+            set_scheme(&mut url, scheme);
+        }
         url.set_path(path);
         url
     }
 
-    fn request_builder(&self, method: Method, path: &str, timeout: Duration) -> RequestBuilder {
-        self.client.request(method, self.url(path)).timeout(timeout)
-    }
-
-    pub fn request(
+    fn request_builder(
         &self,
         method: Method,
+        scheme: Option<Scheme>,
         path: &str,
-        spec: Option<<P::Timeouts as TimeoutsParams>::Key>,
-        xri: &str,
+        timeout: Duration,
     ) -> RequestBuilder {
-        let timeout = self.timeouts[spec.unwrap_or_default()];
-        #[cfg(feature = "callbacks")]
-        self.on_request_building(&method, path, timeout, Some(xri));
-        self.request_builder(method, path, timeout)
-            .header("X-Request-Id", xri)
+        self.client
+            .load()
+            .request(method, self.url(scheme, path))
+            .headers(self.base_headers())
+            .timeout(timeout)
     }
 
-    #[cfg(feature = "pinger")]
-    pub fn set_pinger<B: Behaviour<Handling = P::Handling>>(&mut self) -> bool {
-        let ping_state = match self.ping.take() {
-            None => return false,
-            Some(config) => config,
-        };
-        let ping::Config {
-            path,
-            method,
-            period,
-        } = match ping_state {
-            PingState::Handle(_) => return true,
-            PingState::Config(config) => config,
-        };
-        #[cfg(feature = "callbacks")]
-        self.on_request_building(&method, &path, period, None);
-        let request = self.request_builder(method, &path, period);
-        self.ping = Some(PingState::Handle(pinger::<B>(request, period)));
-        true
+    /// Profile headers along with the current credentials (taking precedence), assembled once
+    /// per credentials change rather than merged anew for every request.
+    fn base_headers(&self) -> HeaderMap {
+        let credentials = self.credentials.load_full();
+        if let Some(assembled) = &*self.assembled.load() {
+            if Arc::ptr_eq(&assembled.credentials, &credentials) {
+                return assembled.headers.clone();
+            }
+        }
+        let mut headers = self.profile.clone();
+        headers.extend(HeaderMap::clone(&credentials));
+        self.assembled.store(Some(Arc::new(AssembledHeaders {
+            credentials,
+            headers: headers.clone(),
+        })));
+        headers
     }
 
-    #[cfg(feature = "callbacks")]
-    fn on_request_building(
+    /// Explicit spec wins, then the method defaults, then the default timeout.
+    fn timeout(
         &self,
         method: &Method,
-        path: &str,
-        timeout: Duration,
-        xri: Option<&str>,
-    ) {
-        P::Callbacks::on_request_building(&RequestInfo {
-            method,
-            path,
-            timeout,
-            xri,
-        });
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Duration {
+        let timeout = match spec {
+            Some(spec) => self.timeouts[spec],
+            None => self
+                .method_timeouts
+                .get(method)
+                .unwrap_or_else(|| self.timeouts[&Default::default()]),
+        };
+        self.capped(timeout)
     }
-}
 
-#[cfg(feature = "pinger")]
-impl<P: Params> Drop for HostInner<P> {
-    fn drop(&mut self) {
-        if let Some(PingState::Handle(handle)) = self.ping.take() {
-            P::Handling::stop(handle)
+    /// The request timeout is applied to every request rather than to the client, since the latter
+    /// would cover the streamed bodies as a whole too.
+    fn capped(&self, timeout: Duration) -> Duration {
+        match self.request_timeout {
+            Some(ceiling) => timeout.min(ceiling),
+            None => timeout,
         }
     }
-}
 
-impl<P: Params> TryFrom<HostConfig<<P::Timeouts as TimeoutsParams>::Key>> for HostInner<P> {
-    type Error = Error;
+    /// Fails if the host is draining or the egress policy denies the request.
+    fn admit(&self, method: &Method, path: &str) -> Result<(), Error> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining);
+        }
+        match &self.egress {
+            Some(policy) => policy.check(method, path),
+            None => Ok(()),
+        }
+    }
 
-    fn try_from(
-        value: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
-    ) -> Result<Self, Self::Error> {
-        Self::new(value)
+    /// Counts the request in flight, then admits it. The request is counted first, so the drain
+    /// started meanwhile either awaits it or gets it refused.
+    fn enter(&self, method: &Method, path: &str) -> Result<InFlightGuard, Error> {
+        let inflight = self.inflight.enter();
+        self.admit(method, path)?;
+        Ok(inflight)
     }
-}
 
-pub struct Host<P: Params = TrivialParams>(Arc<HostInner<P>>);
+    /// Makes the following requests refused, giving the future which awaits the requests in flight.
+    /// The future doesn't refer to the host, so it can be sent whatever the host params are.
+    #[cfg(feature = "tokio")]
+    fn drain(&self, timeout: Duration) -> impl std::future::Future<Output = usize> {
+        self.draining.store(true, Ordering::SeqCst);
+        let inflight = self.inflight.clone();
+        async move {
+            let _ = tokio::time::timeout(timeout, inflight.idle()).await;
+            inflight.count()
+        }
+    }
 
-impl<P: Params> Host<P> {
-    #[cfg(feature = "pinger")]
-    pub fn new<B: Behaviour<Handling = P::Handling>>(
-        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
-    ) -> Result<Self, Error> {
-        let mut inner: HostInner<P> = config.try_into()?;
-        inner.set_pinger::<B>();
-        Ok(Self(Arc::new(inner)))
+    /// Finishes the pingers (letting the pings in flight complete), giving the futures resolved
+    /// once they are stopped.
+    #[cfg(feature = "tokio")]
+    fn finish_pingers(&self) -> Vec<BoxFuture<'_, ()>> {
+        use ping::Handle as _;
+
+        let handles: Vec<_> = self
+            .ping
+            .as_ref()
+            .and_then(PingState::handle)
+            .into_iter()
+            .chain(self.keepalive.as_ref().and_then(PingState::handle))
+            .collect();
+        for handle in &handles {
+            handle.finish();
+        }
+        handles.into_iter().map(|handle| handle.stopped()).collect()
     }
 
-    #[cfg(not(feature = "pinger"))]
-    pub fn new(config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>) -> Result<Self, Error> {
-        Ok(Self(Arc::new(config.try_into()?)))
+    fn health_report(&self) -> HealthReport {
+        HealthReport {
+            address: self.base_url.authority().to_owned(),
+            scheme: self.scheme,
+            #[cfg(feature = "pinger")]
+            ping: match &self.ping {
+                None => PingReport::Disabled,
+                Some(PingState::Config(_)) => PingReport::Configured,
+                Some(PingState::Handle(_)) => PingReport::Running(
+                    *self
+                        .ping_status
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                ),
+            },
+            inflight: self.inflight.count(),
+            #[cfg(feature = "tls-expiry")]
+            certificate: self.certificate.as_ref().and_then(CertificateWatch::last),
+        }
     }
 
-    #[inline]
-    pub fn post(
+    fn set_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        store_credentials(&self.credentials, credentials)?;
+        #[cfg(feature = "events")]
+        emit(&self.events, HostEvent::CredentialsReloaded);
+        Ok(())
+    }
+
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    fn reload_identity(&self) -> Result<bool, Error> {
+        let config = match &self.rebuild {
+            Some(config) => config,
+            None => return Ok(false),
+        };
+        let client = Self::build_client(
+            config,
+            #[cfg(feature = "dns-timings")]
+            &self.resolver,
+        )?;
+        // NOTE: the requests built with the old client meanwhile are sent with the new transport,
+        // which is fine since the built requests don't refer to the client
+        self.transport
+            .store(Arc::new(P::Transport::from_client(client.clone())));
+        self.client.store(Arc::new(client));
+        #[cfg(feature = "events")]
+        emit(&self.events, HostEvent::IdentityReloaded);
+        Ok(true)
+    }
+
+    pub fn request(
         &self,
+        method: Method,
+        scheme: Option<Scheme>,
         path: &str,
-        spec: Option<<P::Timeouts as TimeoutsParams>::Key>,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
         xri: &str,
     ) -> RequestBuilder {
-        self.0.request(Method::POST, path, spec, xri)
+        self.labeled_request(method, scheme, path, spec, xri.into(), &Labels::default())
     }
 
-    #[inline]
-    pub fn get(
+    /// Same as [`HostInner::request`], but with the ready request id.
+    pub fn request_xri(
         &self,
+        method: Method,
+        scheme: Option<Scheme>,
         path: &str,
-        spec: Option<<P::Timeouts as TimeoutsParams>::Key>,
-        xri: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &Xri,
     ) -> RequestBuilder {
-        self.0.request(Method::GET, path, spec, xri)
+        self.labeled_request(method, scheme, path, spec, xri.into(), &Labels::default())
     }
 
-    #[inline]
-    pub fn request(
+    fn labeled_request(
         &self,
         method: Method,
+        scheme: Option<Scheme>,
         path: &str,
-        spec: Option<<P::Timeouts as TimeoutsParams>::Key>,
-        xri: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: XriRef<'_>,
+        #[allow(unused_variables)] labels: &Labels,
     ) -> RequestBuilder {
-        self.0.request(method, path, spec, xri)
+        let timeout = self.timeout(&method, spec);
+        #[cfg(feature = "callbacks")]
+        self.on_request_building(&method, path, timeout, Some(xri.as_str()), labels);
+        #[cfg(feature = "events")]
+        self.on_request_built(&method, path, Some(xri.as_str()), labels);
+        let request = xri.apply(self.request_builder(method, scheme, path, timeout));
+        self.inspect(&request);
+        request
     }
 
-    #[cfg(not(feature = "pinger"))]
-    #[inline]
-    pub fn ping(&self, method: Method, path: &str, timeout: Duration) -> RequestBuilder {
-        self.0.request_builder(method, path, timeout)
+    /// Records the spec of the built request, if requests are inspected.
+    fn inspect(&self, request: &RequestBuilder) {
+        let log = match &self.request_log {
+            Some(log) => log,
+            None => return,
+        };
+        if let Some(spec) = RequestSpec::of(request) {
+            #[cfg(feature = "callbacks")]
+            P::Callbacks::on_request_inspected(&spec);
+            log.record(spec);
+        }
     }
-}
 
-#[cfg(feature = "pinger")]
-impl Default for Host<TrivialParams> {
-    fn default() -> Self {
-        Self::new::<MinimalBehaviour>(HostConfig::<TrivialKey>::default())
-            .expect("Failed creating default Host instance")
+    /// Same as [`HostInner::request`], but keeps the callbacks context to report the response with.
+    #[cfg(feature = "callbacks")]
+    fn observed_request(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: XriRef<'_>,
+        labels: &Labels,
+    ) -> (RequestBuilder, <P::Callbacks as Callbacks>::Context) {
+        let timeout = self.timeout(&method, spec);
+        let context = self.on_request_building(&method, path, timeout, Some(xri.as_str()), labels);
+        #[cfg(feature = "events")]
+        self.on_request_built(&method, path, Some(xri.as_str()), labels);
+        let request = xri.apply(self.request_builder(method, None, path, timeout));
+        self.inspect(&request);
+        (request, context)
     }
-}
 
-#[cfg(not(feature = "pinger"))]
-impl Default for Host<TrivialParams> {
-    fn default() -> Self {
-        Self::new(HostConfig::<TrivialKey>::default())
-            .expect("Failed creating default Host instance")
+    pub fn try_request(
+        &self,
+        method: Method,
+        scheme: Option<Scheme>,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<RequestBuilder, Error> {
+        validate_path(path)?;
+        self.admit(&method, path)?;
+        Ok(self.request(method, scheme, path, spec, xri))
     }
-}
 
-impl<P: Params> Debug for Host<P> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        f.debug_struct("Host")
-            .field("base_url", &self.0.base_url)
-            .finish()
+    pub fn request_with(
+        &self,
+        method: Method,
+        scheme: Option<Scheme>,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        options: &RequestOptions,
+    ) -> Result<RequestBuilder, Error> {
+        self.admit(&method, path)?;
+        self.apply_options(self.request(method, scheme, path, spec, xri), options)
     }
-}
 
-impl<P: Params> Clone for Host<P> {
-    fn clone(&self) -> Self {
-        Self(Arc::clone(&self.0))
+    /// Fails if the options refer to an unknown credentials set or headers profile, or if the
+    /// request is already broken while its credentials are to be overridden.
+    fn apply_options(
+        &self,
+        request: RequestBuilder,
+        options: &RequestOptions,
+    ) -> Result<RequestBuilder, Error> {
+        let mut request = options.apply(request);
+        if let Some(name) = options.profile_name() {
+            let profile = self
+                .profiles
+                .get(name)
+                .ok_or_else(|| Error::UnknownProfile(name.into()))?;
+            request = request.headers(profile.clone());
+        }
+        if let Some(name) = options.credentials_name() {
+            let credentials = self
+                .credential_sets
+                .get(name)
+                .ok_or_else(|| Error::UnknownCredentials(name.into()))?;
+            if options.authorization().is_none() {
+                request = request.headers(credentials.clone());
+            }
+        }
+        if let Some(authorization) = options.authorization() {
+            // NOTE: the builder can't remove headers, so the host credentials are removed from the request itself
+            let (client, built) = request.build_split();
+            let mut built = built.map_err(Error::RequestBuild)?;
+            for name in self.credentials.load().keys() {
+                built.headers_mut().remove(name);
+            }
+            request = authorization.apply(RequestBuilder::from_parts(client, built));
+        }
+        Ok(request)
     }
-}
 
-#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
-pub enum Error {
-    #[error("Failed parsing URL from text '{candidate}': {source}")]
-    UrlParse {
-        candidate: String,
-        source: <Url as FromStr>::Err,
-    },
-    #[error("Failed building HTTP(S) client: {0}")]
-    ClientBulid(#[source] reqwest::Error),
-    #[error(transparent)]
-    CredentialsConvert(credentials::Error),
+    /// Sends the built request through the egress policy, authentication and transport,
+    /// accounting and auditing it on the way.
+    async fn dispatch(
+        &self,
+        request: Request,
+        labels: &Labels,
+        attempts: &mut u32,
+    ) -> Result<Response, SendErrorKind> {
+        self.dispatch_held(request, labels, attempts)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Dispatches the request like [`HostInner::dispatch`] does, but gives the response along with
+    /// the guard keeping the request counted in flight, for the body read by the host itself.
+    async fn dispatch_held(
+        &self,
+        #[allow(unused_mut)] mut request: Request,
+        #[allow(unused_variables)] labels: &Labels,
+        attempts: &mut u32,
+    ) -> Result<(Response, InFlightGuard), SendErrorKind> {
+        let inflight = self
+            .enter(request.method(), request.url().path())
+            .map_err(SendErrorKind::Denied)?;
+        #[cfg(feature = "gzip")]
+        if let Some(compression) = &self.compression {
+            compression.apply(&mut request);
+        }
+        #[cfg(feature = "audit")]
+        let audit = PendingRecord::new(&request, &self.audit, labels);
+        #[cfg(feature = "tokio")]
+        let repeated = self.repeatable(&request);
+        let transport = self.transport.load_full();
+        let result = self.auth.execute(&*transport, request, attempts).await;
+        #[cfg(feature = "tokio")]
+        let result = match (result, repeated) {
+            (Ok(response), Some((repeated, used)))
+                if response.status() == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                match self.reauthorize(repeated, &used).await {
+                    Some(repeated) => self.auth.execute(&*transport, repeated, attempts).await,
+                    None => Ok(response),
+                }
+            }
+            (result, _) => result,
+        };
+        #[cfg(feature = "audit")]
+        if let Some(audit) = audit {
+            audit.finish(&result);
+        }
+        if let Ok(response) = &result {
+            self.observe_clock(response.headers());
+            #[cfg(feature = "tls-expiry")]
+            self.observe_certificate(response);
+        }
+        result.map(|response| (response, inflight))
+    }
+
+    /// Copy of the request to be repeated with the refreshed credentials if the host rejects
+    /// the current ones, along with the credentials it is sent with.
+    #[cfg(feature = "tokio")]
+    fn repeatable(&self, request: &Request) -> Option<(Request, Arc<HeaderMap>)> {
+        if !self.refresh_on_unauthorized || self.refresher.load().is_none() {
+            return None;
+        }
+        let used = self.credentials.load_full();
+        // NOTE: refreshing the default credentials doesn't help requests made with other sets
+        let with_default = used
+            .iter()
+            .all(|(name, value)| request.headers().get_all(name).iter().any(|x| x == value));
+        if !with_default {
+            return None;
+        }
+        Some((request.try_clone()?, used))
+    }
+
+    /// Puts the fresh credentials into the request, fetching them only if nobody replaced
+    /// the used ones yet (so concurrently rejected requests mostly refresh them once).
+    #[cfg(feature = "tokio")]
+    async fn reauthorize(&self, mut request: Request, used: &Arc<HeaderMap>) -> Option<Request> {
+        let current = self.credentials.load_full();
+        let fresh = if Arc::ptr_eq(&current, used) {
+            let refresher = self.refresher.load_full()?;
+            let fresh = Arc::new(refresher().await?);
+            self.credentials.store(fresh.clone());
+            fresh
+        } else {
+            current
+        };
+        let headers = request.headers_mut();
+        for name in used.keys() {
+            headers.remove(name);
+        }
+        headers.extend(HeaderMap::clone(&fresh));
+        Some(request)
+    }
+
+    fn observe_clock(&self, headers: &HeaderMap) {
+        let exceeding = match &self.clock {
+            Some(clock) => clock.sample(headers),
+            None => return,
+        };
+        #[cfg(feature = "callbacks")]
+        if let Some(skew) = exceeding {
+            P::Callbacks::on_clock_skew(skew);
+        }
+        #[cfg(not(feature = "callbacks"))]
+        let _ = exceeding;
+    }
+
+    #[cfg(feature = "tls-expiry")]
+    fn observe_certificate(&self, response: &Response) {
+        let expiring = match &self.certificate {
+            Some(certificate) => certificate.sample(response),
+            None => return,
+        };
+        #[cfg(feature = "callbacks")]
+        if let Some(info) = &expiring {
+            P::Callbacks::on_certificate_expiring(info);
+        }
+        #[cfg(not(feature = "callbacks"))]
+        let _ = expiring;
+    }
+
+    async fn execute<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: XriRef<'_>,
+        options: &RequestOptions,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.execute_counted(method, path, spec, xri, options, prepare, &mut 0)
+            .await
+    }
+
+    /// Executes the request, counting the requests really sent to the host (the repeated ones
+    /// included) into `attempts`.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_counted<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: XriRef<'_>,
+        options: &RequestOptions,
+        prepare: F,
+        attempts: &mut u32,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let fail = |kind| SendError::new(method.clone(), path, xri.as_str(), kind);
+        let labels = options.labels();
+        #[cfg(feature = "callbacks")]
+        let (request, context) = self.observed_request(method.clone(), path, spec, xri, labels);
+        #[cfg(not(feature = "callbacks"))]
+        let request = self.labeled_request(method.clone(), None, path, spec, xri, labels);
+        #[cfg(feature = "events")]
+        let started = Instant::now();
+        let result = match self.apply_options(request, options) {
+            Ok(request) => match prepare(request).build() {
+                Ok(request) => self.dispatch(request, labels, attempts).await.map_err(fail),
+                Err(source) => Err(fail(SendErrorKind::Build(source))),
+            },
+            Err(Error::RequestBuild(source)) => Err(fail(SendErrorKind::Build(source))),
+            Err(source) => Err(fail(SendErrorKind::Denied(source))),
+        };
+        #[cfg(feature = "events")]
+        emit(
+            &self.events,
+            match &result {
+                Ok(response) => HostEvent::Response {
+                    method: method.clone(),
+                    path: path.into(),
+                    xri: xri.as_str().into(),
+                    status: response.status(),
+                    elapsed: started.elapsed(),
+                    labels: labels.clone(),
+                },
+                Err(error) => HostEvent::Error {
+                    method: method.clone(),
+                    path: path.into(),
+                    xri: xri.as_str().into(),
+                    error: error.kind.to_string(),
+                    labels: labels.clone(),
+                },
+            },
+        );
+        #[cfg(feature = "callbacks")]
+        if let Ok(response) = &result {
+            let correlation = self.correlation(response.headers());
+            if !correlation.is_empty() {
+                P::Callbacks::on_response_correlated(&context, &correlation);
+            }
+        }
+        #[cfg(feature = "callbacks")]
+        P::Callbacks::on_response_received(context, result.as_ref().map(Response::status));
+        result
+    }
+
+    /// Response headers listed in [`HostConfig::correlation_headers`] (like the server request IDs).
+    fn correlation(&self, headers: &HeaderMap) -> HeaderMap {
+        self.correlation_headers
+            .iter()
+            .filter_map(|name| Some((name.clone(), headers.get(name)?.clone())))
+            .collect()
+    }
+
+    pub async fn send<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.send_with(
+            method,
+            path,
+            spec,
+            xri.into(),
+            &RequestOptions::default(),
+            prepare,
+        )
+        .await
+    }
+
+    pub async fn send_with<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: XriRef<'_>,
+        options: &RequestOptions,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let response = self
+            .execute(method.clone(), path, spec, xri, options, prepare)
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(SendError::new(
+                method,
+                path,
+                xri.as_str(),
+                SendErrorKind::Status(status),
+            ));
+        }
+        Ok(response)
+    }
+
+    pub async fn send_if_match<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        etag: &HeaderValue,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let options = RequestOptions::default();
+        let response = self
+            .execute(
+                method.clone(),
+                path,
+                spec,
+                xri.into(),
+                &options,
+                |request| prepare(request.header(IF_MATCH, etag)),
+            )
+            .await?;
+        let kind = match response.status() {
+            status if status.is_success() => return Ok(response),
+            reqwest::StatusCode::PRECONDITION_FAILED => {
+                SendErrorKind::PreconditionFailed(etag.clone())
+            }
+            status => SendErrorKind::Status(status),
+        };
+        Err(SendError::new(method, path, xri, kind))
+    }
+
+    async fn send_prepared(&self, request: RequestBuilder) -> Result<Response, SendError> {
+        let request = request.build().map_err(|source| SendError {
+            method: Method::default(),
+            path: source.url().map(Url::path).unwrap_or_default().into(),
+            xri: String::new(),
+            kind: SendErrorKind::Build(source),
+        })?;
+        let (method, path, xri) = request_origin(&request);
+        let response = self
+            .dispatch(request, &Labels::default(), &mut 0)
+            .await
+            .map_err(|kind| SendError::new(method.clone(), &path, &xri, kind))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(SendError::new(
+                method,
+                &path,
+                &xri,
+                SendErrorKind::Status(status),
+            ));
+        }
+        Ok(response)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn get_stream(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, SendError>> + Send + 'static, SendError> {
+        let fail = |kind| SendError::new(Method::GET, path, xri, kind);
+        let timeout = self.timeout(&Method::GET, spec);
+        let mut request = self
+            .request(Method::GET, None, path, spec, xri)
+            .build()
+            .map_err(|source| fail(SendErrorKind::Build(source)))?;
+        // NOTE: the timeout is applied to every chunk below rather than to the whole transfer
+        *request.timeout_mut() = None;
+        let (response, inflight) = tokio::time::timeout(
+            timeout,
+            self.dispatch_held(request, &Labels::default(), &mut 0),
+        )
+        .await
+        .map_err(|_| fail(SendErrorKind::Stalled(timeout)))?
+        .map_err(fail)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(fail(SendErrorKind::Status(status)));
+        }
+        // NOTE: the request is counted in flight until its body is read through or dropped
+        let state = (response, inflight, path.to_owned(), xri.to_owned());
+        Ok(stream::unfold(Some(state), move |state| async move {
+            let (mut response, inflight, path, xri) = state?;
+            let fail = |kind| SendError::new(Method::GET, &path, &xri, kind);
+            match tokio::time::timeout(timeout, response.chunk()).await {
+                Err(_) => Some((Err(fail(SendErrorKind::Stalled(timeout))), None)),
+                Ok(Err(source)) => Some((Err(fail(SendErrorKind::Body(source))), None)),
+                Ok(Ok(Some(chunk))) => Some((Ok(chunk), Some((response, inflight, path, xri)))),
+                Ok(Ok(None)) => None,
+            }
+        }))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn get_ndjson<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<impl Stream<Item = Result<T, SendError>> + Send + 'static, SendError> {
+        let chunks = self.get_stream(path, spec, xri).await?;
+        let origin = (path.to_owned(), xri.to_owned());
+        let state = (Box::pin(chunks), Vec::new(), false, origin);
+        Ok(stream::unfold(
+            state,
+            |(mut chunks, mut buffer, mut done, origin)| async move {
+                loop {
+                    let line = match buffer.iter().position(|byte| *byte == b'\n') {
+                        Some(end) => buffer.drain(..=end).collect(),
+                        None if done => mem::take(&mut buffer),
+                        None => {
+                            match chunks.next().await {
+                                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                                // NOTE: the incomplete line is dropped, since nothing follows it
+                                Some(Err(error)) => {
+                                    return Some((Err(error), (chunks, Vec::new(), true, origin)))
+                                }
+                                None => done = true,
+                            }
+                            continue;
+                        }
+                    };
+                    if line.iter().all(u8::is_ascii_whitespace) {
+                        if done && buffer.is_empty() {
+                            return None;
+                        }
+                        continue;
+                    }
+                    // NOTE: a malformed line fails alone, the lines following it are still parsed
+                    let item = <Json as Decode<T>>::decode(&line).map_err(|source| {
+                        let (path, xri) = &origin;
+                        SendError::new(Method::GET, path, xri, SendErrorKind::Decode(source))
+                    });
+                    return Some((item, (chunks, buffer, done, origin)));
+                }
+            },
+        ))
+    }
+
+    pub async fn send_batch<I>(&self, requests: I, max_parallel: usize) -> BatchResults
+    where
+        I: IntoIterator<Item = RequestBuilder>,
+    {
+        BatchResults(
+            stream::iter(requests)
+                .map(|request| self.send_prepared(request))
+                .buffered(max_parallel.max(1))
+                .collect()
+                .await,
+        )
+    }
+
+    pub async fn warmup(&self, connections: usize, path: &str) -> Vec<Result<Duration, SendError>> {
+        future::join_all((0..connections).map(|index| async move {
+            let started = Instant::now();
+            let xri = format!("warmup-{}", index);
+            self.execute(
+                Method::HEAD,
+                path,
+                None,
+                xri.as_str().into(),
+                &RequestOptions::default(),
+                |request| request,
+            )
+            .await
+            .map(|_| started.elapsed())
+        }))
+        .await
+    }
+
+    /// Dispatches the request on behalf of all the callers awaiting the flight, sharing the outcome
+    /// with them. The request is dispatched by the caller itself rather than by the shared future,
+    /// since the latter has to outlive the caller.
+    async fn fly(&self, request: Request, pilot: oneshot::Sender<Landing>) -> Landing {
+        let (method, path, xri) = request_origin(&request);
+        let fail = |kind| Arc::new(SendError::new(method.clone(), &path, &xri, kind));
+        let landing = match self
+            .dispatch_held(request, &Labels::default(), &mut 0)
+            .await
+        {
+            Ok((response, _)) if !response.status().is_success() => {
+                Err(fail(SendErrorKind::Status(response.status())))
+            }
+            Ok((response, _inflight)) => FullResponse::receive(response)
+                .await
+                .map_err(|source| fail(SendErrorKind::Body(source))),
+            Err(kind) => Err(fail(kind)),
+        };
+        let _ = pilot.send(landing.clone());
+        landing
+    }
+
+    pub async fn send_deduped<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        key: &str,
+        prepare: F,
+    ) -> Result<FullResponse, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let fail = |kind| SendError::new(method.clone(), path, xri, kind);
+        let request = self
+            .request(method.clone(), None, path, spec, xri)
+            .header(IDEMPOTENCY_KEY_HEADER, key);
+        let request = prepare(request)
+            .build()
+            .map_err(|source| fail(SendErrorKind::Build(source)))?;
+        self.admit(request.method(), request.url().path())
+            .map_err(|error| fail(SendErrorKind::Denied(error)))?;
+        let window = match self.dedupe_window {
+            Some(window) => window,
+            None => {
+                let (_, pilot) = take_off();
+                return self
+                    .fly(request, pilot)
+                    .await
+                    .map_err(|source| fail(SendErrorKind::Shared(source)));
+            }
+        };
+        let entry = (method.clone(), path.to_owned(), key.to_owned());
+        let pilot = loop {
+            let flight = {
+                let mut deduped = self
+                    .deduped
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let now = Instant::now();
+                deduped.retain(|_, (started, flight)| {
+                    now.duration_since(*started) < window
+                        && !is_abandoned(flight)
+                        && !has_failed(flight)
+                });
+                match deduped.get(&entry) {
+                    Some((_, flight)) => flight.clone(),
+                    None => {
+                        let (flight, pilot) = take_off();
+                        deduped.insert(entry.clone(), (now, flight));
+                        break pilot;
+                    }
+                }
+            };
+            if let Some(landing) = flight.await {
+                return landing.map_err(|source| fail(SendErrorKind::Shared(source)));
+            }
+        };
+        self.fly(request, pilot)
+            .await
+            .map_err(|source| fail(SendErrorKind::Shared(source)))
+    }
+
+    pub async fn get_shared<F>(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<FullResponse, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let fail = |kind| SendError::new(Method::GET, path, xri, kind);
+        let request = prepare(self.request(Method::GET, None, path, spec, xri))
+            .build()
+            .map_err(|source| fail(SendErrorKind::Build(source)))?;
+        self.admit(request.method(), request.url().path())
+            .map_err(|error| fail(SendErrorKind::Denied(error)))?;
+        let key = request.url().to_string();
+        let (flight, landing) = loop {
+            let (flight, pilot) = {
+                let mut flights = self
+                    .flights
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                match flights.get(&key) {
+                    Some(flight) if !is_abandoned(flight) => (flight.clone(), None),
+                    _ => {
+                        let (flight, pilot) = take_off();
+                        flights.insert(key.clone(), flight.clone());
+                        (flight, Some(pilot))
+                    }
+                }
+            };
+            match pilot {
+                Some(pilot) => break (flight, self.fly(request, pilot).await),
+                None => {
+                    if let Some(landing) = flight.clone().await {
+                        break (flight, landing);
+                    }
+                }
+            }
+        };
+        let mut flights = self
+            .flights
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if flights.get(&key).is_some_and(|x| x.ptr_eq(&flight)) {
+            flights.remove(&key);
+        }
+        landing.map_err(|source| fail(SendErrorKind::Shared(source)))
+    }
+
+    #[cfg(feature = "cache")]
+    pub async fn get_cached(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<CachedResponse, SendError> {
+        let fail = |kind| SendError::new(Method::GET, path, xri, kind);
+        let bypass = self.cache_config.bypasses(path);
+        let cached = if bypass { None } else { self.cache.get(path) };
+        if let Some(cached) = cached
+            .as_ref()
+            .filter(|cached| cached.is_fresh(std::time::SystemTime::now()))
+        {
+            return Ok(cached.clone());
+        }
+        #[cfg(feature = "tokio")]
+        if let Some(stale) = cached
+            .as_ref()
+            .filter(|cached| self.is_stale_usable(cached))
+        {
+            self.refresh_in_background(path, spec, xri, stale);
+            return Ok(stale.clone());
+        }
+        let response = self
+            .execute(
+                Method::GET,
+                path,
+                spec,
+                xri.into(),
+                &RequestOptions::default(),
+                |request| match &cached {
+                    Some(cached) => cached.condition(request),
+                    None => request,
+                },
+            )
+            .await?;
+        let status = response.status();
+        match cached {
+            Some(mut cached) if status == reqwest::StatusCode::NOT_MODIFIED => {
+                cached.refresh(response.headers());
+                self.cache.put(path, cached.clone());
+                return Ok(cached);
+            }
+            _ if !status.is_success() => return Err(fail(SendErrorKind::Status(status))),
+            _ => (),
+        }
+        let fresh = FullResponse::receive(response)
+            .await
+            .map_err(|source| fail(SendErrorKind::Body(source)))?;
+        if !bypass && fresh.is_cacheable() {
+            self.cache.put(path, fresh.clone());
+        }
+        Ok(fresh)
+    }
+
+    #[cfg(all(feature = "cache", feature = "tokio"))]
+    fn is_stale_usable(&self, cached: &CachedResponse) -> bool {
+        match self.cache_config.stale_while_revalidate {
+            Some(window) => cached.is_stale_usable(std::time::SystemTime::now(), window),
+            None => false,
+        }
+    }
+
+    /// Revalidates the cached response like [`HostInner::get_cached`] does, but in the spawned task,
+    /// unless the path is being revalidated already. Failures just keep the stale response cached.
+    #[cfg(all(feature = "cache", feature = "tokio"))]
+    fn refresh_in_background(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        stale: &CachedResponse,
+    ) {
+        let mut refreshing = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if refreshing.contains(path) {
+            return;
+        }
+        let inflight = match self.enter(&Method::GET, path) {
+            Ok(inflight) => inflight,
+            Err(_) => return,
+        };
+        let request = match stale
+            .condition(self.request(Method::GET, None, path, spec, xri))
+            .build()
+        {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+        refreshing.insert(path.into());
+        drop(refreshing);
+        let (transport, auth) = (self.transport.load_full(), self.auth.clone());
+        let (cache, refreshing) = (self.cache.clone(), self.refreshing.clone());
+        let (path, mut stale) = (path.to_owned(), stale.clone());
+        #[cfg(feature = "audit")]
+        let audit = PendingRecord::new(&request, &self.audit, &Labels::default());
+        tokio::spawn(async move {
+            let _inflight = inflight;
+            let result = auth.execute(&*transport, request, &mut 0).await;
+            #[cfg(feature = "audit")]
+            if let Some(audit) = audit {
+                audit.finish(&result);
+            }
+            match result {
+                Ok(response) if response.status() == reqwest::StatusCode::NOT_MODIFIED => {
+                    stale.refresh(response.headers());
+                    cache.put(&path, stale);
+                }
+                Ok(response) if response.status().is_success() => {
+                    if let Ok(fresh) = FullResponse::receive(response).await {
+                        if fresh.is_cacheable() {
+                            cache.put(&path, fresh);
+                        }
+                    }
+                }
+                _ => (),
+            }
+            refreshing
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .remove(&path);
+        });
+    }
+
+    pub async fn send_as<T, C, F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<T, SendError>
+    where
+        C: Decode<T>,
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let fail = |kind| SendError::new(method.clone(), path, xri, kind);
+        let body = self
+            .send(method.clone(), path, spec, xri, |request| {
+                let request = if self.profile.contains_key(ACCEPT) {
+                    request
+                } else {
+                    request.header(ACCEPT, C::CONTENT_TYPE)
+                };
+                prepare(request)
+            })
+            .await?
+            .bytes()
+            .await
+            .map_err(|source| fail(SendErrorKind::Body(source)))?;
+        C::decode(&body).map_err(|source| fail(SendErrorKind::Decode(source)))
+    }
+
+    pub async fn send_body_as<T, C, B>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        body: &B,
+    ) -> Result<T, SendError>
+    where
+        C: Decode<T> + Encode<B>,
+        B: ?Sized,
+    {
+        let body = C::encode(body).map_err(|source| {
+            SendError::new(method.clone(), path, xri, SendErrorKind::Encode(source))
+        })?;
+        self.send_as::<T, C, _>(method, path, spec, xri, |request| {
+            request.header(CONTENT_TYPE, C::CONTENT_TYPE).body(body)
+        })
+        .await
+    }
+
+    pub async fn send_reported<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> (Result<FullResponse, SendError>, SendReport)
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let started = Instant::now();
+        let mut report = SendReport {
+            xri: xri.into(),
+            attempts: 0,
+            url: None,
+            dns: None,
+            ttfb: None,
+            total: Duration::ZERO,
+            correlation: HeaderMap::new(),
+        };
+        let options = RequestOptions::default();
+        let result = match self
+            .execute_counted(
+                method.clone(),
+                path,
+                spec,
+                xri.into(),
+                &options,
+                prepare,
+                &mut report.attempts,
+            )
+            .await
+        {
+            // NOTE: the server IDs are worth the most for the failed requests, so they are kept anyway
+            Ok(response) if !response.status().is_success() => {
+                report.correlation = self.correlation(response.headers());
+                Err(SendError::new(
+                    method,
+                    path,
+                    xri,
+                    SendErrorKind::Status(response.status()),
+                ))
+            }
+            Ok(response) => {
+                report.ttfb = Some(started.elapsed());
+                report.url = Some(response.url().clone());
+                report.correlation = self.correlation(response.headers());
+                FullResponse::receive(response).await.map_err(|source| {
+                    SendError::new(method, path, xri, SendErrorKind::Body(source))
+                })
+            }
+            Err(error) => Err(error),
+        };
+        report.total = started.elapsed();
+        #[cfg(feature = "dns-timings")]
+        {
+            report.dns = self
+                .base_url
+                .host_str()
+                .and_then(|host| self.resolver.resolved_since(host, started));
+        }
+        (result, report)
+    }
+
+    pub async fn send_json<T, F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<T, SendError>
+    where
+        T: DeserializeOwned,
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        let fail = |kind| SendError::new(method.clone(), path, xri, kind);
+        self.send(method.clone(), path, spec, xri, prepare)
+            .await?
+            .json::<P::Envelope<T>>()
+            .await
+            .map_err(|source| fail(SendErrorKind::Body(source)))?
+            .open()
+            .map_err(|fail_reason| fail(SendErrorKind::Rejected(fail_reason.to_string())))
+    }
+
+    #[cfg(feature = "pinger")]
+    pub fn set_pinger<B: Behaviour<Handling = P::Handling>>(
+        &mut self,
+        process_error: B::ProcessError,
+    ) -> bool {
+        let ping_state = match self.ping.take() {
+            None => return false,
+            Some(config) => config,
+        };
+        let config = match ping_state {
+            PingState::Handle(handle) => {
+                self.ping = Some(PingState::Handle(handle));
+                return true;
+            }
+            PingState::Config(config) => config,
+        };
+        let (probes, settings) = self.ping_setup(config);
+        let (first_ping, first_ping_receiver) = oneshot::channel();
+        self.first_ping = Some(first_ping_receiver.shared());
+        #[cfg(feature = "callbacks")]
+        let handle = pinger::<B, P::Callbacks>(
+            probes,
+            settings,
+            process_error,
+            self.ping_status.clone(),
+            first_ping,
+            #[cfg(feature = "events")]
+            self.events.clone(),
+        );
+        #[cfg(not(feature = "callbacks"))]
+        let handle = pinger::<B>(
+            probes,
+            settings,
+            process_error,
+            self.ping_status.clone(),
+            first_ping,
+            #[cfg(feature = "events")]
+            self.events.clone(),
+        );
+        self.ping = Some(PingState::Handle(handle));
+        true
+    }
+
+    /// Builds the probes and pinger settings of the ping config.
+    #[cfg(feature = "pinger")]
+    fn ping_setup(&self, config: ping::Config) -> (Vec<ping::Probe>, ping::Settings) {
+        let timeout = config.timeout();
+        let max_period = config.max_period();
+        let ping::Config {
+            path,
+            method,
+            period,
+            scheme,
+            mode,
+            weight,
+            probes,
+            aggregation,
+            ..
+        } = config;
+        let main = ping::ProbeConfig {
+            path,
+            method,
+            mode,
+            weight,
+        };
+        let probes = std::iter::once(main)
+            .chain(probes)
+            .map(|probe| self.ping_probe(probe, scheme, timeout))
+            .collect();
+        let settings = ping::Settings {
+            period,
+            max_period,
+            aggregation,
+            address: self.base_url.authority().to_owned(),
+        };
+        (probes, settings)
+    }
+
+    #[cfg(feature = "pinger")]
+    fn ping_probe(
+        &self,
+        config: ping::ProbeConfig,
+        scheme: Option<Scheme>,
+        timeout: Duration,
+    ) -> ping::Probe {
+        let ping::ProbeConfig {
+            path,
+            method,
+            mode,
+            weight,
+        } = config;
+        #[cfg(feature = "callbacks")]
+        self.on_request_building(&method, &path, timeout, None, &Labels::default());
+        #[cfg(feature = "events")]
+        self.on_request_built(&method, &path, None, &Labels::default());
+        let method = match mode {
+            ping::Mode::Head => Method::HEAD,
+            ping::Mode::Answer | ping::Mode::Range => method,
+        };
+        let mut request = self.request_builder(method, scheme, &path, timeout);
+        if mode == ping::Mode::Range {
+            request = request.header(reqwest::header::RANGE, "bytes=0-0");
+        }
+        ping::Probe {
+            request,
+            mode,
+            path,
+            weight,
+        }
+    }
+
+    #[cfg(feature = "pinger")]
+    pub fn set_keeper<B: Behaviour<Handling = P::Handling>>(&mut self) -> bool {
+        let ping::KeepaliveConfig {
+            period,
+            path,
+            method,
+        } = match self.keepalive.take() {
+            None => return false,
+            Some(PingState::Handle(handle)) => {
+                self.keepalive = Some(PingState::Handle(handle));
+                return true;
+            }
+            Some(PingState::Config(config)) => config,
+        };
+        #[cfg(feature = "callbacks")]
+        self.on_request_building(&method, &path, period, None, &Labels::default());
+        #[cfg(feature = "events")]
+        self.on_request_built(&method, &path, None, &Labels::default());
+        let request = self.request_builder(method, None, &path, period);
+        self.keepalive = Some(PingState::Handle(keeper::<B>(request, period)));
+        true
+    }
+
+    #[cfg(feature = "callbacks")]
+    fn on_request_building(
+        &self,
+        method: &Method,
+        path: &str,
+        timeout: Duration,
+        xri: Option<&str>,
+        labels: &Labels,
+    ) -> <P::Callbacks as Callbacks>::Context {
+        P::Callbacks::on_request_building(&RequestInfo {
+            method,
+            path,
+            timeout,
+            xri,
+            labels,
+        })
+    }
+}
+
+#[cfg(feature = "events")]
+impl<P: Params> HostInner<P> {
+    fn on_request_built(&self, method: &Method, path: &str, xri: Option<&str>, labels: &Labels) {
+        emit(
+            &self.events,
+            HostEvent::RequestBuilt {
+                method: method.clone(),
+                path: path.into(),
+                xri: xri.map(Into::into),
+                labels: labels.clone(),
+            },
+        );
+    }
+}
+
+#[cfg(feature = "pinger")]
+impl<P: Params> Drop for HostInner<P> {
+    fn drop(&mut self) {
+        if let Some(PingState::Handle(handle)) = self.ping.take() {
+            P::Handling::stop(handle)
+        }
+        if let Some(PingState::Handle(handle)) = self.keepalive.take() {
+            P::Handling::stop(handle)
+        }
+    }
+}
+
+impl<P: Params> TryFrom<HostConfig<<P::Timeouts as TimeoutsParams>::Key>> for HostInner<P> {
+    type Error = Error;
+
+    fn try_from(
+        value: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+pub struct Host<P: Params = TrivialParams>(Arc<HostInner<P>>);
+
+impl<P: Params> Host<P> {
+    #[cfg(feature = "pinger")]
+    pub fn new<B: Behaviour<Handling = P::Handling>>(
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Error>
+    where
+        B::ProcessError: Default,
+    {
+        Self::with_ping_handler::<B>(config, B::ProcessError::default())
+    }
+
+    /// Same as [`Host::new`], but uses the given ping errors handler instead of the default one.
+    #[cfg(feature = "pinger")]
+    pub fn with_ping_handler<B: Behaviour<Handling = P::Handling>>(
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        process_error: B::ProcessError,
+    ) -> Result<Self, Error> {
+        let mut inner: HostInner<P> = config.try_into()?;
+        inner.set_pinger::<B>(process_error);
+        inner.set_keeper::<B>();
+        Ok(Self(Arc::new(inner)))
+    }
+
+    #[cfg(not(feature = "pinger"))]
+    pub fn new(config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>) -> Result<Self, Error> {
+        Ok(Self(Arc::new(config.try_into()?)))
+    }
+
+    /// Same as [`Host::new`], but uses the given client (e.g. with custom connector or middleware)
+    /// instead of building one, so the client settings of the config (extra settings,
+    /// strict scheme) and the user agent of the params are not applied.
+    #[cfg(feature = "pinger")]
+    pub fn from_client<B: Behaviour<Handling = P::Handling>>(
+        client: Client,
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Error>
+    where
+        B::ProcessError: Default,
+    {
+        let mut inner = HostInner::<P>::with_client(config, Some(client))?;
+        inner.set_pinger::<B>(B::ProcessError::default());
+        inner.set_keeper::<B>();
+        Ok(Self(Arc::new(inner)))
+    }
+
+    /// Same as [`Host::new`], but uses the given client (e.g. with custom connector or middleware)
+    /// instead of building one, so the client settings of the config (extra settings,
+    /// strict scheme) and the user agent of the params are not applied.
+    #[cfg(not(feature = "pinger"))]
+    pub fn from_client(
+        client: Client,
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Error> {
+        Ok(Self(Arc::new(HostInner::with_client(
+            config,
+            Some(client),
+        )?)))
+    }
+
+    /// Same as [`Host::new`], but also sends structured events about requests, responses,
+    /// pings and alike into the given channel.
+    #[cfg(all(feature = "events", feature = "pinger"))]
+    pub fn with_events<B: Behaviour<Handling = P::Handling>>(
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        events: EventSender,
+    ) -> Result<Self, Error>
+    where
+        B::ProcessError: Default,
+    {
+        let mut inner: HostInner<P> = config.try_into()?;
+        inner.events = Some(events);
+        inner.set_pinger::<B>(B::ProcessError::default());
+        inner.set_keeper::<B>();
+        Ok(Self(Arc::new(inner)))
+    }
+
+    /// Same as [`Host::new`], but also sends structured events about requests, responses
+    /// and alike into the given channel.
+    #[cfg(all(feature = "events", not(feature = "pinger")))]
+    pub fn with_events(
+        config: HostConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        events: EventSender,
+    ) -> Result<Self, Error> {
+        let mut inner: HostInner<P> = config.try_into()?;
+        inner.events = Some(events);
+        Ok(Self(Arc::new(inner)))
+    }
+
+    #[inline]
+    pub fn post(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.0.request(Method::POST, None, path, spec, xri)
+    }
+
+    #[inline]
+    pub fn get(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.0.request(Method::GET, None, path, spec, xri)
+    }
+
+    /// Same as [`Host::post`] with the given timeouts key.
+    #[inline]
+    pub fn post_spec(
+        &self,
+        path: &str,
+        spec: &<P::Timeouts as TimeoutsParams>::Key,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.post(path, Some(spec), xri)
+    }
+
+    /// Same as [`Host::post`] without any timeouts key (so the method or the default one is used).
+    #[inline]
+    pub fn post_default(&self, path: &str, xri: &str) -> RequestBuilder {
+        self.post(path, None, xri)
+    }
+
+    /// Same as [`Host::get`] with the given timeouts key.
+    #[inline]
+    pub fn get_spec(
+        &self,
+        path: &str,
+        spec: &<P::Timeouts as TimeoutsParams>::Key,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.get(path, Some(spec), xri)
+    }
+
+    /// Same as [`Host::get`] without any timeouts key (so the method or the default one is used).
+    #[inline]
+    pub fn get_default(&self, path: &str, xri: &str) -> RequestBuilder {
+        self.get(path, None, xri)
+    }
+
+    /// Same as [`Host::request`] with the given timeouts key.
+    #[inline]
+    pub fn request_spec(
+        &self,
+        method: Method,
+        path: &str,
+        spec: &<P::Timeouts as TimeoutsParams>::Key,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.request(method, path, Some(spec), xri)
+    }
+
+    /// Same as [`Host::request`] without any timeouts key (so the method or the default one is used).
+    #[inline]
+    pub fn request_default(&self, method: Method, path: &str, xri: &str) -> RequestBuilder {
+        self.request(method, path, None, xri)
+    }
+
+    /// Builds the request with no checks: neither the egress policy nor the drain applies to it,
+    /// see [`Host::try_request`] for the checked one.
+    #[inline]
+    pub fn request(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.0.request(method, None, path, spec, xri)
+    }
+
+    /// Same as [`Host::request`], but with the ready request id (see [`Xri`]).
+    #[inline]
+    pub fn request_xri(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &Xri,
+    ) -> RequestBuilder {
+        self.0.request_xri(method, None, path, spec, xri)
+    }
+
+    /// Replaces the credentials used by all the requests built after the call, without
+    /// rebuilding the client (so pooled connections are kept). Note that the requests built
+    /// before (including the pinger and keepalive ones) keep using the previous credentials.
+    #[inline]
+    pub fn set_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        self.0.set_credentials(credentials)
+    }
+
+    /// Rebuilds the client with the identity files (see [`HostConfig::identity`]) read anew and
+    /// swaps it in once built, so the requests are served by the old client meanwhile (and those
+    /// in flight finish with it). The old client is kept on failure. Gives false if the host has no
+    /// identity or uses the client given ready-made. Reads the files in place, so better be called
+    /// off the async runtime threads, like [`IdentityWatcher`] does.
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[inline]
+    pub fn reload_identity(&self) -> Result<bool, Error> {
+        self.0.reload_identity()
+    }
+
+    /// Makes the request of the host from the `http` one: the host base URL, credentials, profile
+    /// and timeout are applied, while method, path, query, headers and body are taken from the given request.
+    #[cfg(feature = "http")]
+    pub fn request_from_http<B: Into<reqwest::Body>>(
+        &self,
+        request: http::Request<B>,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<RequestBuilder, Error> {
+        let (parts, body) = request.into_parts();
+        let path = parts.uri.path();
+        let mut builder = self
+            .0
+            .try_request(parts.method.clone(), None, path, spec, xri)?;
+        if let Some(query) = parts.uri.query() {
+            let pairs: Vec<(String, String)> =
+                serde_urlencoded::from_str(query).map_err(|_| Error::MalformedPath {
+                    path: parts.uri.to_string(),
+                    reason: "contains malformed query string",
+                })?;
+            builder = builder.query(&pairs);
+        }
+        Ok(builder.headers(parts.headers).body(body))
+    }
+
+    /// Replaces the sink (the one from config, if any) audit records of the following requests are written to.
+    #[cfg(feature = "audit")]
+    #[inline]
+    pub fn set_audit_sink<S: AuditSink + 'static>(&self, sink: S) {
+        self.0.audit.store(Some(Arc::new(Box::new(sink))));
+    }
+
+    /// Current state of the host (address, ping results, requests in flight) to be reported
+    /// by readiness endpoints.
+    #[inline]
+    pub fn health_report(&self) -> HealthReport {
+        self.0.health_report()
+    }
+
+    /// Sends HEAD request to the host root just to see its TLS certificate (e.g. for hosts rarely
+    /// requested otherwise), and gives the certificate seen. Gives `None` if the certificate is not
+    /// monitored (see [`HostConfig::tls_expiry`]) or the connection is not a TLS one.
+    #[cfg(feature = "tls-expiry")]
+    pub async fn probe_certificate(&self) -> Result<Option<CertificateInfo>, SendError> {
+        self.0
+            .execute(
+                Method::HEAD,
+                "/",
+                None,
+                "certificate-probe".into(),
+                &RequestOptions::default(),
+                |request| request,
+            )
+            .await?;
+        Ok(self.0.certificate.as_ref().and_then(CertificateWatch::last))
+    }
+
+    /// Makes all the following requests fail fast with [`Error::Draining`] (the refused sends
+    /// fail with [`SendErrorKind::Denied`]), then waits up to the timeout for the requests
+    /// in flight to finish. Gives the number of requests still in flight, so zero means
+    /// the host is drained completely. Draining can't be undone.
+    ///
+    /// Only the requests of the fallible builders (like [`Host::try_request`]) and the sending
+    /// methods (like [`Host::send`]) are refused and awaited: the ones built with the infallible
+    /// builders (like [`Host::get`]) are sent by reqwest directly, so the host doesn't see them.
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.0.drain(timeout).await
+    }
+
+    /// Subscribes the host to the shutdown, which drains it and finishes its pingers
+    /// (see [`Shutdown::run`]). The shutdown keeps the host alive until run.
+    #[cfg(feature = "tokio")]
+    pub fn subscribe(&self, shutdown: &Shutdown)
+    where
+        Self: Send + Sync + 'static,
+    {
+        let host = self.clone();
+        shutdown.subscribe(Box::new(move |timeout| {
+            let host = host.clone();
+            // NOTE: the host is drained first, then its pingers are finished
+            async move {
+                let drained = host.0.drain(timeout);
+                let inflight = drained.await;
+                let stopped = host.0.finish_pingers();
+                future::join_all(stopped).await;
+                inflight
+            }
+            .boxed()
+        }));
+    }
+
+    /// Specs of the latest requests built by the host (oldest first), if enabled with
+    /// [`HostConfig::inspect_requests`]; empty otherwise.
+    #[inline]
+    pub fn inspected_requests(&self) -> Vec<RequestSpec> {
+        self.0
+            .request_log
+            .as_ref()
+            .map(RequestLog::specs)
+            .unwrap_or_default()
+    }
+
+    /// Interns the path (giving the same id for the same path every time), so the requests
+    /// made with it (as `&id`) in tight loops skip building its URL anew. Meant for the few
+    /// endpoints hit most often: the interned paths are kept as long as the host is.
+    #[inline]
+    pub fn intern_path(&self, path: &str) -> PathId {
+        self.0.paths.intern(path, |path| self.0.url(None, path))
+    }
+
+    /// Observations made on the host traffic (like the host clock skew).
+    #[inline]
+    pub fn runtime_info(&self) -> RuntimeInfo {
+        RuntimeInfo {
+            clock_skew: self.0.clock.as_ref().and_then(ClockWatch::last),
+        }
+    }
+
+    /// Class of the failed request according to the host params.
+    #[inline]
+    pub fn classify(&self, error: &SendError) -> ErrorClass {
+        P::classify(error)
+    }
+
+    /// Policy (timeout along with retry, backoff and hedging settings) configured for the given spec,
+    /// so callers and retrying layers can follow it. The timeout is cut down to the request timeout, if any.
+    #[inline]
+    pub fn policy(&self, spec: Option<&<P::Timeouts as TimeoutsParams>::Key>) -> Policy {
+        let policy = self.0.timeouts.get(spec);
+        Policy {
+            timeout: self.0.capped(policy.timeout),
+            ..*policy
+        }
+    }
+
+    /// Same as [`Host::get`], but also appends the given parameters to the query string.
+    /// Fails if the host is draining or the egress policy denies the request.
+    #[inline]
+    pub fn get_with_query<Q: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        query: &Q,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<RequestBuilder, Error> {
+        self.0.admit(&Method::GET, path)?;
+        Ok(self
+            .0
+            .request(Method::GET, None, path, spec, xri)
+            .query(query))
+    }
+
+    /// Same as [`Host::request`], but also applies the per-request options.
+    /// Fails if the options refer to an unknown credentials set.
+    #[inline]
+    pub fn request_with(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        options: &RequestOptions,
+    ) -> Result<RequestBuilder, Error> {
+        self.0.request_with(method, None, path, spec, xri, options)
+    }
+
+    /// Same as [`Host::request`], but uses the given scheme instead of the host-wide one.
+    /// The strict host (see [`HostConfig::strict_scheme`]) refuses to send it unless it is HTTPS,
+    /// and WebSocket schemes can't be sent by reqwest at all, so they only make sense for
+    /// the URL (see [`Host::url`]).
+    #[inline]
+    pub fn request_with_scheme(
+        &self,
+        scheme: Scheme,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> RequestBuilder {
+        self.0.request(method, Some(scheme), path, spec, xri)
+    }
+
+    /// Same as [`Host::request`], but rejects malformed paths (not starting with '/',
+    /// containing query string, fragment, whitespace and alike) instead of producing
+    /// an unexpected URL.
+    #[inline]
+    pub fn try_request(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<RequestBuilder, Error> {
+        self.0.try_request(method, None, path, spec, xri)
+    }
+
+    /// Builds the request with the host settings, lets `prepare` complete it (e.g. set the body)
+    /// and sends it, treating non-successful statuses as errors.
+    #[inline]
+    pub async fn send<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0.send(method, path, spec, xri, prepare).await
+    }
+
+    /// Same as [`Host::send`], but only if the resource still matches the ETag (e.g. the one of
+    /// [`FullResponse::etag`] received before), failing with [`SendErrorKind::PreconditionFailed`] otherwise.
+    /// Suits the optimistic concurrency control of PUT and PATCH requests.
+    #[inline]
+    pub async fn send_if_match<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        etag: &HeaderValue,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0
+            .send_if_match(method, path, spec, xri, etag, prepare)
+            .await
+    }
+
+    /// Same as [`Host::send`], but also applies the per-request options, reporting their labels
+    /// to the callbacks, events and audit records. Fails if the options refer to an unknown
+    /// credentials set or headers profile.
+    #[inline]
+    pub async fn send_with<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        options: &RequestOptions,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0
+            .send_with(method, path, spec, xri.into(), options, prepare)
+            .await
+    }
+
+    /// Same as [`Host::send`], but with the ready request id, so the many requests made
+    /// on behalf of the same incoming one share its header value.
+    #[inline]
+    pub async fn send_xri<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &Xri,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0
+            .send_with(
+                method,
+                path,
+                spec,
+                xri.into(),
+                &RequestOptions::default(),
+                prepare,
+            )
+            .await
+    }
+
+    /// Same as [`Host::send`], but also asks for the response body in the format of the codec `C`
+    /// (using Accept header) and decodes it. The host-wide Accept (see [`HostConfig::accept`])
+    /// takes precedence over the codec one, being meant for the media types the codec still decodes
+    /// (like the version-pinned `application/vnd.foo.v2+json` for [`Json`]). Either of them is
+    /// replaced by the one set in `prepare` with [`RequestBuilder::headers`].
+    #[inline]
+    pub async fn send_as<T, C, F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<T, SendError>
+    where
+        C: Decode<T>,
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0
+            .send_as::<T, C, F>(method, path, spec, xri, prepare)
+            .await
+    }
+
+    /// Same as [`Host::send_as`], but also encodes the request body with the codec `C`.
+    #[inline]
+    pub async fn send_body_as<T, C, B>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        body: &B,
+    ) -> Result<T, SendError>
+    where
+        C: Decode<T> + Encode<B>,
+        B: ?Sized,
+    {
+        self.0
+            .send_body_as::<T, C, B>(method, path, spec, xri, body)
+            .await
+    }
+
+    /// Same as [`Host::send`], but also receives the response body and reports timings
+    /// and other circumstances of the sending.
+    #[inline]
+    pub async fn send_reported<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> (Result<FullResponse, SendError>, SendReport)
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0.send_reported(method, path, spec, xri, prepare).await
+    }
+
+    /// Same as [`Host::send`], but also deserializes the answer and opens its envelope
+    /// (see [`Params::Envelope`]) to get the payload.
+    #[inline]
+    pub async fn send_json<T, F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<T, SendError>
+    where
+        T: DeserializeOwned,
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0.send_json(method, path, spec, xri, prepare).await
+    }
+
+    /// Repeats GET requests answered with JSON arrays of items (opened with the envelope as usual),
+    /// yielding the items one by one. The next request carries the query parameter given by
+    /// `cursor_extractor` for the last item received (if any). Failures are yielded too, right away,
+    /// while the next request is made after the backoff (of the policy of the spec, or its timeout
+    /// if no backoff is set) doubled with every consecutive failure, or right away the longest one
+    /// if classified as throttling. The stream never ends, so just drop it to stop polling.
+    #[cfg(feature = "tokio")]
+    pub fn long_poll<'a, T, F>(
+        &'a self,
+        path: &'a str,
+        spec: Option<&'a <P::Timeouts as TimeoutsParams>::Key>,
+        xri: &'a str,
+        cursor_extractor: F,
+    ) -> impl Stream<Item = Result<T, SendError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+        F: FnMut(&T) -> Option<(String, String)> + 'a,
+    {
+        let policy = self.policy(spec);
+        let backoff = if policy.backoff.is_zero() {
+            policy.timeout
+        } else {
+            policy.backoff
+        };
+        let state = (cursor_extractor, None, VecDeque::new(), 0u32, None);
+        stream::unfold(
+            state,
+            move |(mut extract, mut cursor, mut items, mut fails, mut delay)| {
+                async move {
+                    loop {
+                        if let Some(item) = items.pop_front() {
+                            return Some((Ok(item), (extract, cursor, items, fails, delay)));
+                        }
+                        if let Some(delay) = delay.take() {
+                            tokio::time::sleep(delay).await;
+                        }
+                        let result = self
+                            .send_json::<Vec<T>, _>(Method::GET, path, spec, xri, |request| {
+                                match &cursor {
+                                    Some(pair) => request.query(&[pair]),
+                                    None => request,
+                                }
+                            })
+                            .await;
+                        match result {
+                            Ok(batch) => {
+                                fails = 0;
+                                if let Some(last) = batch.last() {
+                                    cursor = extract(last).or(cursor);
+                                }
+                                items.extend(batch);
+                            }
+                            Err(error) => {
+                                // NOTE: the shift is limited to keep the delay sane and avoid overflow
+                                let shift = match P::classify(&error) {
+                                    ErrorClass::Throttle => MAX_BACKOFF_SHIFT,
+                                    ErrorClass::Retryable | ErrorClass::Fatal => {
+                                        fails.min(MAX_BACKOFF_SHIFT)
+                                    }
+                                };
+                                delay =
+                                    Some(backoff.checked_mul(1 << shift).unwrap_or(Duration::MAX));
+                                fails += 1;
+                                return Some((Err(error), (extract, cursor, items, fails, delay)));
+                            }
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// Sends requests (previously made with this host) with at most `max_parallel` of them
+    /// being in flight at once. Results are in the same order the requests were given.
+    /// Note that errors of requests failed to build contain neither method nor X-Request-Id.
+    #[inline]
+    pub async fn send_batch<I>(&self, requests: I, max_parallel: usize) -> BatchResults
+    where
+        I: IntoIterator<Item = RequestBuilder>,
+    {
+        self.0.send_batch(requests, max_parallel).await
+    }
+
+    /// Opens (at most) `connections` connections to the host at once by sending concurrent HEAD
+    /// requests to the given path, to avoid latency spikes on the first real requests.
+    /// Any response counts as success, otherwise the connection is considered failed.
+    /// Gives time taken by each request.
+    #[inline]
+    pub async fn warmup(&self, connections: usize, path: &str) -> Vec<Result<Duration, SendError>> {
+        self.0.warmup(connections, path).await
+    }
+
+    /// Sends GET request giving the response body as a stream of chunks. The timeout (chosen as usual)
+    /// applies to the response headers and then to every chunk, so long transfers are not killed
+    /// unless stalled.
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub async fn get_stream(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, SendError>> + Send + 'static, SendError> {
+        self.0.get_stream(path, spec, xri).await
+    }
+
+    /// Sends GET request giving the response body as a stream of items parsed from its lines
+    /// (newline-delimited JSON). Lines are parsed as soon as received and the body is read
+    /// no further than the stream is polled. Blank lines are skipped, and a malformed line yields
+    /// a decoding error without ending the stream. Timeouts apply as in [`Self::get_stream`].
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub async fn get_ndjson<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<impl Stream<Item = Result<T, SendError>> + Send + 'static, SendError> {
+        self.0.get_ndjson(path, spec, xri).await
+    }
+
+    /// Sends request with the given `Idempotency-Key` header. If the dedupe window is configured,
+    /// the requests with the same method, path and key sent within the window after the first one
+    /// are not sent at all: they get the response of the first one, awaiting it if still in flight.
+    /// The first one failed is forgotten once it lands (its duplicates in flight get the failure),
+    /// so it can be retried with the same key. This guards against accidental double sends
+    /// of unsafe requests by the caller.
+    #[inline]
+    pub async fn send_deduped<F>(
+        &self,
+        method: Method,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        key: &str,
+        prepare: F,
+    ) -> Result<FullResponse, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0
+            .send_deduped(method, path, spec, xri, key, prepare)
+            .await
+    }
+
+    /// Sends GET request, sharing it with all concurrent identical (same URL including query)
+    /// requests made through this method: only one of them really reaches the host, and all of them
+    /// receive the same response. Note that the request is sent with the `X-Request-Id` of
+    /// the first caller and the headers set by `prepare` of later callers are ignored.
+    #[inline]
+    pub async fn get_shared<F>(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+        prepare: F,
+    ) -> Result<FullResponse, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.0.get_shared(path, spec, xri, prepare).await
+    }
+
+    /// Makes GET request conditional using validators (ETag, Last-Modified) of previously cached
+    /// response for the same path, returning the cached response if the server answers with 304.
+    /// The cached response still fresh (by its Cache-Control or Expires headers) is returned
+    /// without any request at all. Paths bypassed with [`CacheConfig::bypass`] are just requested.
+    /// With [`CacheConfig::stale_while_revalidate`] set (and the `tokio` feature), the stale response
+    /// within the window is returned at once too, while being revalidated in the background.
+    #[cfg(feature = "cache")]
+    #[inline]
+    pub async fn get_cached(
+        &self,
+        path: &str,
+        spec: Option<&<P::Timeouts as TimeoutsParams>::Key>,
+        xri: &str,
+    ) -> Result<CachedResponse, SendError> {
+        self.0.get_cached(path, spec, xri).await
+    }
+
+    /// Full URL for the given path, optionally with the host-wide scheme overridden
+    /// (e.g. to hand it over to a WebSocket client using `ws` or `wss`).
+    #[inline]
+    pub fn url(&self, scheme: Option<Scheme>, path: &str) -> Url {
+        self.0.url(scheme, path)
+    }
+
+    /// Handle of the pinger, if it was configured and spawned.
+    #[cfg(feature = "pinger")]
+    pub fn ping_handle(&self) -> Option<&<P::Handling as Handling>::Handle> {
+        match &self.0.ping {
+            Some(PingState::Handle(handle)) => Some(handle),
+            _ => None,
+        }
+    }
+
+    /// Pings once with the configured probes right away, regardless of the pinger
+    /// (e.g. for pre-flight checks). Gives `None` if ping is not configured.
+    #[cfg(feature = "pinger")]
+    pub async fn check_now<B: Behaviour>(
+        &self,
+    ) -> Option<ping::CheckReport<<B::Answer as ping::Answer>::Fail>> {
+        let config = self.0.ping_config.clone()?;
+        let (probes, settings) = self.0.ping_setup(config);
+        Some(ping::check::<B>(probes, settings.aggregation).await)
+    }
+
+    /// Resolves with the host health once the first ping is finished (the pinger pings right
+    /// on start), or with `None` if the pinger is not spawned or stopped before.
+    #[cfg(feature = "pinger")]
+    pub async fn first_ping(&self) -> Option<Health> {
+        self.0.first_ping.clone()?.await.ok()
+    }
+
+    #[cfg(not(feature = "pinger"))]
+    #[inline]
+    pub fn ping(&self, method: Method, path: &str, timeout: Duration) -> RequestBuilder {
+        self.0.request_builder(method, None, path, timeout)
+    }
+}
+
+impl Host<TrivialParams> {
+    /// Fallible replacement of [`Default::default`], which panics on failure.
+    #[cfg(feature = "pinger")]
+    pub fn try_default() -> Result<Self, Error> {
+        Self::new::<MinimalBehaviour>(HostConfig::<TrivialKey>::default())
+    }
+
+    /// Fallible replacement of [`Default::default`], which panics on failure.
+    #[cfg(not(feature = "pinger"))]
+    pub fn try_default() -> Result<Self, Error> {
+        Self::new(HostConfig::<TrivialKey>::default())
+    }
+}
+
+/// Panics if the host can't be created (e.g. the TLS backend fails to initialize),
+/// so it is gated with the `panicking-default` feature. See [`Host::try_default`].
+#[cfg(feature = "panicking-default")]
+impl Default for Host<TrivialParams> {
+    fn default() -> Self {
+        Self::try_default().expect("Failed creating default Host instance")
+    }
+}
+
+impl<P: Params> Debug for Host<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Host")
+            .field("base_url", &self.0.base_url)
+            .finish()
+    }
+}
+
+impl<P: Params> Clone for Host<P> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
+pub enum Error {
+    #[error("Failed parsing URL from text '{candidate}': {source}")]
+    UrlParse {
+        candidate: String,
+        source: <Url as FromStr>::Err,
+    },
+    #[error("Failed building HTTP(S) client: {0}")]
+    ClientBulid(#[source] reqwest::Error),
+    #[error(transparent)]
+    CredentialsConvert(credentials::Error),
+    #[error("Malformed request path '{path}': {reason}")]
+    MalformedPath { path: String, reason: &'static str },
+    #[error("Unknown credentials set '{0}'")]
+    UnknownCredentials(String),
+    #[error("Unknown headers profile '{0}'")]
+    UnknownProfile(String),
+    #[error("Failed building request: {0}")]
+    RequestBuild(#[source] reqwest::Error),
+    #[error("Header '{header}' of profile '{profile}' has invalid name or value")]
+    InvalidProfileHeader { profile: String, header: String },
+    #[error("Default header '{header}' has invalid value '{value}'")]
+    InvalidDefaultHeader { header: HeaderName, value: String },
+    #[error("Invalid correlation header name '{0}'")]
+    InvalidCorrelationHeader(String),
+    #[error("Invalid HTTP method name '{0}'")]
+    InvalidMethod(String),
+    #[error("Invalid request id '{0}', only visible ASCII is allowed")]
+    InvalidXri(String),
+    #[error("Failed creating host '{name}': {source}")]
+    NamedHost { name: String, source: Box<Error> },
+    #[error("Unknown config profile '{0}'")]
+    UnknownConfigProfile(String),
+    #[error("hosts.{name}: {source}")]
+    InvalidHostConfig {
+        name: String,
+        source: serde_path_to_error::Error<serde_json::Error>,
+    },
+    #[error("Request {method} '{path}' is denied by the egress policy")]
+    PolicyDenied { method: Method, path: String },
+    #[error("Host is draining and accepts no new requests")]
+    Draining,
+    #[cfg(feature = "pinger")]
+    #[error("Ping timeout {timeout:?} exceeds the ping period {period:?}")]
+    PingTimeout { timeout: Duration, period: Duration },
+    #[cfg(feature = "pinger")]
+    #[error("Ping maximum period {max_period:?} is less than the ping period {period:?}")]
+    PingMaxPeriod {
+        max_period: Duration,
+        period: Duration,
+    },
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[error("Failed reading client identity file '{}': {source}", path.display())]
+    IdentityFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[error("Invalid client identity: {0}")]
+    InvalidIdentity(#[source] reqwest::Error),
+    #[cfg(feature = "tls-pinning")]
+    #[error("Invalid SPKI pin '{0}', base64 of SHA-256 digest is expected")]
+    InvalidSpkiPin(String),
+    #[cfg(feature = "tls-pinning")]
+    #[error("Failed configuring TLS with pinned certificates: {0}")]
+    PinnedTlsConfig(String),
+    #[cfg(feature = "audit")]
+    #[error("Failed opening audit file '{}': {source}", path.display())]
+    AuditFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// Whether repeating the same request later (or with another instance of the host) has
+    /// a chance to succeed: only the draining host is, the other failures are caused by
+    /// configuration (or the arguments of the caller), so only changing them helps.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Draining => true,
+            Self::NamedHost { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
 }
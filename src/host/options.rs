@@ -0,0 +1,203 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING},
+    RequestBuilder,
+};
+use serde::{Serialize, Serializer};
+
+use crate::secret::Secret;
+
+/// The most labels a request may carry, so whatever they are reported to stays bounded.
+pub const MAX_LABELS: usize = 8;
+
+/// Labels attributing a request (e.g. to a feature or tenant), reported to the callbacks,
+/// events and audit records of the request. Keys are static to keep their set bounded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Labels(Vec<(&'static str, String)>);
+
+impl Labels {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.0.iter().map(|(key, value)| (*key, value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Replaces the value of the key already present.
+    fn set(&mut self, key: &'static str, value: String) -> Result<(), OptionsError> {
+        if let Some((_, old)) = self.0.iter_mut().find(|(name, _)| *name == key) {
+            *old = value;
+            return Ok(());
+        }
+        if self.0.len() >= MAX_LABELS {
+            return Err(OptionsError::TooManyLabels(key));
+        }
+        self.0.push((key, value));
+        Ok(())
+    }
+}
+
+impl Display for Labels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (index, (key, value)) in self.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Labels {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Per-request settings, applied on top of the host-wide ones.
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    query: Vec<(String, String)>,
+    credentials: Option<String>,
+    profile: Option<String>,
+    headers: HeaderMap,
+    labels: Labels,
+    authorization: Option<Authorization>,
+}
+
+/// Authorization of the single request replacing the host credentials.
+#[derive(Clone, Debug)]
+pub(crate) enum Authorization {
+    Bearer(Secret<String>),
+    Basic(String, Secret<String>),
+}
+
+impl Authorization {
+    pub(crate) fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Self::Bearer(token) => request.bearer_auth(token.expose()),
+            Self::Basic(user, password) => request.basic_auth(user, Some(password.expose())),
+        }
+    }
+}
+
+impl RequestOptions {
+    /// Appends the parameters (a struct, map or sequence of pairs) to the query string
+    /// of the request, encoding them properly.
+    pub fn query<Q: Serialize + ?Sized>(mut self, query: &Q) -> Result<Self, OptionsError> {
+        let encoded = serde_urlencoded::to_string(query).map_err(OptionsError::Query)?;
+        // NOTE: parsing of the string just encoded never fails
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(&encoded).unwrap_or_default();
+        self.query.extend(pairs);
+        Ok(self)
+    }
+
+    /// Selects the named credentials set (see [`HostConfig::credential_sets`](super::HostConfig::credential_sets))
+    /// to authenticate the request with.
+    pub fn credentials<S: Into<String>>(mut self, name: S) -> Self {
+        self.credentials = Some(name.into());
+        self
+    }
+
+    pub(crate) fn credentials_name(&self) -> Option<&str> {
+        self.credentials.as_deref()
+    }
+
+    /// Authenticates the request with the bearer token (e.g. the user-delegated one to be
+    /// forwarded) instead of the host credentials and the selected credentials set.
+    pub fn bearer<T: Display>(mut self, token: T) -> Self {
+        self.authorization = Some(Authorization::Bearer(token.to_string().into()));
+        self
+    }
+
+    /// Authenticates the request with the basic auth instead of the host credentials
+    /// and the selected credentials set.
+    pub fn basic<U: Display, P: Display>(mut self, user: U, password: P) -> Self {
+        self.authorization = Some(Authorization::Basic(
+            user.to_string(),
+            password.to_string().into(),
+        ));
+        self
+    }
+
+    pub(crate) fn authorization(&self) -> Option<&Authorization> {
+        self.authorization.as_ref()
+    }
+
+    /// Selects the named headers profile (see [`HostConfig::profiles`](super::HostConfig::profiles))
+    /// to add to the request, overriding the host-wide profile headers of the same names.
+    pub fn profile<S: Into<String>>(mut self, name: S) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    pub(crate) fn profile_name(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Overrides the host-wide Accept header (see [`HostConfig::accept`](super::HostConfig::accept)).
+    pub fn accept(mut self, value: HeaderValue) -> Self {
+        self.headers.insert(ACCEPT, value);
+        self
+    }
+
+    /// Overrides the host-wide Accept-Encoding header (see [`HostConfig::accept_encoding`](super::HostConfig::accept_encoding)).
+    pub fn accept_encoding(mut self, value: HeaderValue) -> Self {
+        self.headers.insert(ACCEPT_ENCODING, value);
+        self
+    }
+
+    /// Keeps the request body uncompressed (see [`HostConfig::compression`](super::HostConfig::compression))
+    /// by stating the identity Content-Encoding explicitly.
+    pub fn uncompressed(mut self) -> Self {
+        self.headers
+            .insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+        self
+    }
+
+    /// Sets the label of the request (see [`Labels`]), failing if there are [`MAX_LABELS`] others already.
+    pub fn label<S: Into<String>>(
+        mut self,
+        key: &'static str,
+        value: S,
+    ) -> Result<Self, OptionsError> {
+        self.labels.set(key, value.into())?;
+        Ok(self)
+    }
+
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    pub(crate) fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if !self.query.is_empty() {
+            request = request.query(&self.query);
+        }
+        if !self.headers.is_empty() {
+            request = request.headers(self.headers.clone());
+        }
+        request
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OptionsError {
+    #[error("Failed encoding query parameters: {0}")]
+    Query(#[source] serde_urlencoded::ser::Error),
+    #[error("Failed setting label '{0}': too many labels already")]
+    TooManyLabels(&'static str),
+}
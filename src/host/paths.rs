@@ -0,0 +1,81 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::Deref,
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use reqwest::Url;
+
+/// Path interned by the host (see [`Host::intern_path`](super::Host::intern_path)). Cheap to clone
+/// and dereferences to the path itself, so `&id` can be passed wherever the path is expected:
+/// the requests made with an interned path reuse its URL built once instead of parsing it anew.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(Arc<str>);
+
+impl PathId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for PathId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PathId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for PathId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for PathId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+/// URLs of the interned paths, rarely added to but read for every request.
+#[derive(Default)]
+pub(crate) struct PathTable(ArcSwap<HashMap<PathId, Url>>);
+
+impl PathTable {
+    /// Gives the existing id of the path, or interns it with the URL made by the function.
+    pub(crate) fn intern<F: Fn(&str) -> Url>(&self, path: &str, url: F) -> PathId {
+        if let Some((id, _)) = self.0.load().get_key_value(path) {
+            return id.clone();
+        }
+        let id = PathId(Arc::from(path));
+        let url = url(path);
+        // NOTE: the map is copied on the update, so readers never wait for the writers
+        self.0.rcu(|urls| {
+            let mut urls = HashMap::clone(urls);
+            urls.entry(id.clone()).or_insert_with(|| url.clone());
+            urls
+        });
+        self.0
+            .load()
+            .get_key_value(path)
+            .map_or(id, |(id, _)| id.clone())
+    }
+
+    pub(crate) fn url(&self, path: &str) -> Option<Url> {
+        let urls = self.0.load();
+        if urls.is_empty() {
+            return None;
+        }
+        urls.get(path).cloned()
+    }
+}
@@ -0,0 +1,310 @@
+use std::{convert::TryFrom, error::Error as StdError, io, sync::Arc, time::SystemTime};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "tls-rustls-webpki")]
+use rustls::OwnedTrustAnchor;
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, CertificateError, ClientConfig, PrivateKey, RootCertStore, ServerName,
+};
+use rustls_pemfile::Item;
+use sha2::{Digest, Sha256};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use super::{Error, IdentityConfig, TlsVersion};
+
+/// Failure of the TLS handshake with the host which certificate has none of the pinned
+/// SPKI digests (see [`ExtraSettings::pinned_spki_sha256`](super::ExtraSettings::pinned_spki_sha256)).
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Failed matching certificate of '{server}' against the pinned SPKI digests, its own is '{found}'")]
+pub struct SpkiPinMismatch {
+    pub server: String,
+    /// Base64 SHA-256 digest of the server certificate SPKI, ready to be pinned if trusted.
+    pub found: String,
+}
+
+impl SpkiPinMismatch {
+    /// Finds the mismatch among the sources of the error (like the [`reqwest::Error`] of the request).
+    pub fn find<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a Self> {
+        let mut current = Some(error);
+        while let Some(error) = current {
+            // NOTE: the handshake failure is wrapped into io::Error, which hides it from the sources
+            let handshake = error
+                .downcast_ref::<io::Error>()
+                .and_then(io::Error::get_ref)
+                .and_then(|inner| inner.downcast_ref::<rustls::Error>());
+            if let Some(rustls::Error::InvalidCertificate(CertificateError::Other(other))) =
+                handshake
+            {
+                if let Some(mismatch) = other.downcast_ref::<Self>() {
+                    return Some(mismatch);
+                }
+            }
+            current = error.source();
+        }
+        None
+    }
+}
+
+/// Builds the rustls config checking the pins on top of the usual certificate checks. Reqwest
+/// ignores its own TLS settings (including the identity) for the preconfigured TLS, so the config
+/// repeats them.
+pub(crate) fn client_config(
+    pins: &[String],
+    min_version: Option<TlsVersion>,
+    identity: Option<&IdentityConfig>,
+) -> Result<ClientConfig, Error> {
+    let pins = pins
+        .iter()
+        .map(|pin| {
+            STANDARD
+                .decode(pin)
+                .ok()
+                .and_then(|digest| <[u8; 32]>::try_from(digest.as_slice()).ok())
+                .ok_or_else(|| Error::InvalidSpkiPin(pin.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+
+    #[allow(unused_mut)]
+    let mut roots = RootCertStore::empty();
+    #[cfg(feature = "tls-rustls-webpki")]
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    #[cfg(feature = "tls-rustls")]
+    for certificate in rustls_native_certs::load_native_certs()
+        .map_err(|source| Error::PinnedTlsConfig(source.to_string()))?
+    {
+        // NOTE: native stores often have ancient malformed roots, which are skipped like reqwest does
+        let _ = roots.add(&Certificate(certificate.0));
+    }
+
+    // NOTE: rustls supports only TLS 1.2 and 1.3, so only the latter is worth restricting to
+    let versions: &[_] = match min_version {
+        Some(TlsVersion::Tls1_3) => &[&rustls::version::TLS13],
+        _ => rustls::ALL_VERSIONS,
+    };
+    let builder = ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(versions)
+        .map_err(|source| Error::PinnedTlsConfig(source.to_string()))?
+        .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+            inner: WebPkiVerifier::new(roots, None),
+            pins,
+        }));
+    let mut config = match identity {
+        Some(identity) => {
+            let (certs, key) = client_auth(identity)?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|source| Error::PinnedTlsConfig(source.to_string()))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Certificate chain and private key of the identity, read the way reqwest reads them.
+fn client_auth(identity: &IdentityConfig) -> Result<(Vec<Certificate>, PrivateKey), Error> {
+    let (cert, key) = identity.read()?;
+    let (mut certs, mut keys) = (Vec::new(), Vec::new());
+    for pem in [cert, key].iter() {
+        let mut reader = pem.as_slice();
+        while let Some(item) = rustls_pemfile::read_one(&mut reader)
+            .map_err(|source| Error::PinnedTlsConfig(source.to_string()))?
+        {
+            match item {
+                Item::X509Certificate(cert) => certs.push(Certificate(cert)),
+                Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => {
+                    keys.push(PrivateKey(key))
+                }
+                _ => {}
+            }
+        }
+    }
+    match (keys.pop(), certs.is_empty()) {
+        (Some(key), false) => Ok((certs, key)),
+        _ => Err(Error::PinnedTlsConfig(
+            "private key or certificate of the identity not found".into(),
+        )),
+    }
+}
+
+/// Usual certificate verifier, additionally requiring the end-entity certificate to be pinned.
+/// The intermediates are not matched, since the ones the server sends are not necessarily
+/// on the path verified (any extra certificate could be appended to pass the check otherwise).
+struct PinnedVerifier {
+    inner: WebPkiVerifier,
+    pins: Vec<[u8; 32]>,
+}
+
+impl PinnedVerifier {
+    fn is_pinned(&self, der: &[u8]) -> bool {
+        spki_digest(der).is_some_and(|digest| self.pins.contains(&digest))
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+        if self.is_pinned(&end_entity.0) {
+            return Ok(verified);
+        }
+        let mismatch = SpkiPinMismatch {
+            server: match server_name {
+                ServerName::DnsName(name) => name.as_ref().to_owned(),
+                ServerName::IpAddress(address) => address.to_string(),
+                other => format!("{:?}", other),
+            },
+            found: spki_digest(&end_entity.0)
+                .map(|digest| STANDARD.encode(digest))
+                .unwrap_or_default(),
+        };
+        Err(rustls::Error::InvalidCertificate(CertificateError::Other(
+            Arc::new(mismatch),
+        )))
+    }
+}
+
+/// SHA-256 digest of the certificate SubjectPublicKeyInfo.
+fn spki_digest(der: &[u8]) -> Option<[u8; 32]> {
+    let (_, certificate) = X509Certificate::from_der(der).ok()?;
+    Some(Sha256::digest(certificate.public_key().raw).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{iter, time::Duration};
+
+    use x509_parser::pem::parse_x509_pem;
+
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBfjCCASSgAwIBAgIBATAKBggqhkjOPQQDAjAeMRwwGgYDVQQDDBNiaWxsaW5n
+LmV4YW1wbGUuY29tMB4XDTI2MTAxNzA4MTc1N1oXDTM2MTAxNDA4MTc1N1owHjEc
+MBoGA1UEAwwTYmlsbGluZy5leGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqGSM49
+AwEHA0IABAQqNU2glWX1wn1xdGWGiNfhitwIzuzCdURP0fz2qKq3NpLQ6iShn3Zz
+vL2MvNYOBrj7x2T3B7nq0YTbTrc6pd2jUzBRMB0GA1UdDgQWBBR9w0dz9a0WBV4a
+LNpwVMVEU35cjjAfBgNVHSMEGDAWgBR9w0dz9a0WBV4aLNpwVMVEU35cjjAPBgNV
+HRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIQCVex8GiX7k8mpYMMygHODm
+MK9+H5rVzS8Y9bFNShatJwIgePkpitfUxW6nOvKGEd0IOv44ZaqcjUsPEZLdUNzI
+UUs=
+-----END CERTIFICATE-----
+";
+    const PIN: &str = "bLmeTGogsB54qHVEBAob7VHGDL9oJR+2b3+ibCwMILs=";
+    /// CA trusted by the client, yet not the one the pinned certificate is issued by.
+    const ROGUE_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBVjCB/aADAgECAgEBMAoGCCqGSM49BAMCMBMxETAPBgNVBAMMCFJvZ3VlIENB
+MB4XDTI2MTAxNzAwMDAwMFoXDTM2MTAxNDAwMDAwMFowEzERMA8GA1UEAwwIUm9n
+dWUgQ0EwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAASIRk4Xk7eOc6FMx0BQz1cM
+sPHgeNZn7OstNbKBYQWuq+l/0owFCMVTPUXG52bD5H7YAHijuB2gO/AtXi6XfeSh
+o0IwQDAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwICBDAdBgNVHQ4EFgQU
+0nr3I1+UGtWOHtZEGrYOfSw8F2IwCgYIKoZIzj0EAwIDSAAwRQIgTqgAX15/1067
+bsFiMIv8LSUnYXIm0PlKqOxfLTHZq0ACIQDZfBJbfIa3Ij8vb5k+fr6tejERJsiW
+Hq6ANzy/59j3Nw==
+-----END CERTIFICATE-----
+";
+    const ROGUE_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBtjCCAV2gAwIBAgIBAjAKBggqhkjOPQQDAjATMREwDwYDVQQDDAhSb2d1ZSBD
+QTAeFw0yNjEwMTcwMDAwMDBaFw0zNjEwMTQwMDAwMDBaMB4xHDAaBgNVBAMME2Jp
+bGxpbmcuZXhhbXBsZS5jb20wWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAASeOEdN
+X2CrR03M9viRs4KMDu5MK28DcwegP4VUqiH12QvKkY0zJV9nhPZZG/DFhI5sX//J
+UhW4/zCcR+2au+1to4GWMIGTMAwGA1UdEwEB/wQCMAAwDgYDVR0PAQH/BAQDAgeA
+MBMGA1UdJQQMMAoGCCsGAQUFBwMBMB4GA1UdEQQXMBWCE2JpbGxpbmcuZXhhbXBs
+ZS5jb20wHQYDVR0OBBYEFFgU/mih0rdYhwwYkB8IFfdI1xigMB8GA1UdIwQYMBaA
+FNJ69yNflBrVjh7WRBq2Dn0sPBdiMAoGCCqGSM49BAMCA0cAMEQCIHKE+Lrh4vDN
+0HBmmcMa1NSMrceJXTRcmy28KZdpsFKdAiBwppHmBDNB0SQfl0wRfhjUGFRe1J+Y
+xEtwsSv7RwuBhA==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn spki_pins_are_matched() {
+        let (_, pem) = parse_x509_pem(CERTIFICATE.as_bytes()).expect("PEM should be parsed");
+        let digest = spki_digest(&pem.contents).expect("Certificate should be parsed");
+        assert_eq!(STANDARD.encode(digest), PIN);
+
+        let verifier = PinnedVerifier {
+            inner: WebPkiVerifier::new(RootCertStore::empty(), None),
+            pins: vec![[0; 32], digest],
+        };
+        assert!(verifier.is_pinned(&pem.contents));
+        assert!(!verifier.is_pinned(b"not a certificate"));
+
+        assert!(client_config(&[PIN.to_owned()], None, None).is_ok());
+        assert!(matches!(
+            client_config(&["bm90IGEgZGlnZXN0".to_owned()], None, None),
+            Err(Error::InvalidSpkiPin(_))
+        ));
+    }
+
+    #[test]
+    fn pins_are_matched_against_end_entity_only() {
+        let der = |pem: &str| {
+            let (_, pem) = parse_x509_pem(pem.as_bytes()).expect("PEM should be parsed");
+            Certificate(pem.contents)
+        };
+        let (leaf, intermediates) = (der(ROGUE_LEAF), [der(CERTIFICATE)]);
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&der(ROGUE_CA))
+            .expect("Root certificate should be added");
+        let verify = |pin: &Certificate| {
+            let verifier = PinnedVerifier {
+                inner: WebPkiVerifier::new(roots.clone(), None),
+                pins: vec![spki_digest(&pin.0).expect("Certificate should be parsed")],
+            };
+            let name = ServerName::try_from("billing.example.com").expect("Name should be valid");
+            // NOTE: the certificates are valid since 2026-10-17 for ten years
+            let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+            // NOTE: the pinned certificate is appended as an unrelated extra intermediate
+            verifier.verify_server_cert(&leaf, &intermediates, &name, &mut iter::empty(), &[], now)
+        };
+
+        assert!(verify(&leaf).is_ok());
+        let error =
+            verify(&intermediates[0]).expect_err("Certificate not pinned should be refused");
+        assert!(matches!(
+            error,
+            rustls::Error::InvalidCertificate(CertificateError::Other(other))
+                if other.downcast_ref::<SpkiPinMismatch>().is_some()
+        ));
+    }
+
+    #[test]
+    fn pin_mismatch_is_found_in_sources() {
+        let mismatch = SpkiPinMismatch {
+            server: "billing.example.com".into(),
+            found: PIN.into(),
+        };
+        let handshake =
+            rustls::Error::InvalidCertificate(CertificateError::Other(Arc::new(mismatch.clone())));
+        let error = io::Error::new(io::ErrorKind::InvalidData, handshake);
+        assert_eq!(SpkiPinMismatch::find(&error), Some(&mismatch));
+
+        let error = io::Error::new(io::ErrorKind::InvalidData, "other failure");
+        assert_eq!(SpkiPinMismatch::find(&error), None);
+    }
+}
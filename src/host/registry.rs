@@ -0,0 +1,289 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::RwLock,
+};
+
+use serde::{
+    de::{DeserializeOwned, Error as DeError},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
+
+use super::{Error, HealthReport, Host, HostConfig, Params, TrivialParams};
+use crate::timeoutsmap::Params as TimeoutsParams;
+
+#[cfg(feature = "callbacks")]
+use super::Callbacks;
+#[cfg(feature = "pinger")]
+use crate::ping::Behaviour;
+
+/// Named hosts of the same kind, to be looked up by name and reported all at once. Hosts can
+/// also be looked up by aliases (like `billing` for `billing-v2`), to migrate the code between
+/// the versions of the upstream service gradually.
+pub struct HostRegistry<P: Params = TrivialParams> {
+    hosts: RwLock<HashMap<String, Host<P>>>,
+    aliases: RwLock<HashMap<String, String>>,
+}
+
+/// Health of every registered host by its name, e.g. for a single upstreams status endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct RegistrySnapshot(pub BTreeMap<String, HealthReport>);
+
+/// Configs of the named hosts, given as the `hosts` table along with the optional `defaults`
+/// one: every host config inherits the fields of `defaults` it doesn't set itself (nested
+/// tables, like `timeouts`, are merged field by field, while any other value is replaced as a whole).
+/// Deserialized with no profile applied, see [`HostsDocument::resolve`] to pick one.
+/// ```toml
+/// [defaults]
+/// scheme = "https"
+/// timeouts = { default = "1s" }
+///
+/// [hosts.billing]
+/// target = "billing.local:8443"
+///
+/// [hosts.search-v2]
+/// target = "search.local:8080"
+/// scheme = "http"
+/// timeouts = { slow = "5s" }
+///
+/// [aliases]
+/// search = "search-v2"
+///
+/// [profile.staging.hosts.billing]
+/// target = "billing.staging.local:8443"
+/// ```
+#[derive(Clone, Debug)]
+pub struct HostsConfig<K: Eq + Hash + Default> {
+    pub hosts: BTreeMap<String, HostConfig<K>>,
+    /// Deprecated names of the hosts, see [`HostRegistry::alias`].
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl<'de, K: Eq + Hash + Default> Deserialize<'de> for HostsConfig<K>
+where
+    HostConfig<K>: DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HostsDocument::deserialize(deserializer)?
+            .resolve(None)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Multi-host config as written, before the defaults and the profile are applied. Profiles
+/// (named after the environments, like `staging`) are given in the `profile` table, each
+/// with its own `defaults`, `hosts` and `aliases` overriding the common ones. Not to be confused
+/// with the headers profiles of [`HostConfig::profiles`].
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct HostsDocument {
+    #[serde(flatten)]
+    pub common: HostsOverrides,
+    #[serde(default)]
+    pub profile: BTreeMap<String, HostsOverrides>,
+}
+
+/// Part of [`HostsDocument`], either common or of some profile.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct HostsOverrides {
+    #[serde(default)]
+    pub defaults: Map<String, Value>,
+    #[serde(default)]
+    pub hosts: BTreeMap<String, Map<String, Value>>,
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl HostsDocument {
+    /// Applies the profile (if any) over the common part, then the defaults to every host.
+    /// Fails if the profile is unknown or any host config is invalid.
+    pub fn resolve<K: Eq + Hash + Default>(
+        self,
+        profile: Option<&str>,
+    ) -> Result<HostsConfig<K>, Error>
+    where
+        HostConfig<K>: DeserializeOwned,
+    {
+        let HostsDocument {
+            common:
+                HostsOverrides {
+                    mut defaults,
+                    mut hosts,
+                    mut aliases,
+                },
+            profile: mut profiles,
+        } = self;
+        if let Some(profile) = profile {
+            let overrides = profiles
+                .remove(profile)
+                .ok_or_else(|| Error::UnknownConfigProfile(profile.into()))?;
+            overlay(&mut defaults, overrides.defaults);
+            for (name, fields) in overrides.hosts {
+                overlay(hosts.entry(name).or_default(), fields);
+            }
+            aliases.extend(overrides.aliases);
+        }
+        let hosts = hosts
+            .into_iter()
+            .map(|(name, fields)| {
+                let mut merged = defaults.clone();
+                overlay(&mut merged, fields);
+                match serde_path_to_error::deserialize(Value::Object(merged)) {
+                    Ok(config) => Ok((name, config)),
+                    Err(source) => Err(Error::InvalidHostConfig { name, source }),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(HostsConfig { hosts, aliases })
+    }
+}
+
+/// Puts the fields over the base ones, merging the tables present in both.
+fn overlay(base: &mut Map<String, Value>, fields: Map<String, Value>) {
+    for (name, field) in fields {
+        match (base.get_mut(&name), field) {
+            (Some(Value::Object(base)), Value::Object(field)) => overlay(base, field),
+            (_, field) => {
+                base.insert(name, field);
+            }
+        }
+    }
+}
+
+impl<P: Params> Default for HostRegistry<P> {
+    fn default() -> Self {
+        Self {
+            hosts: RwLock::default(),
+            aliases: RwLock::default(),
+        }
+    }
+}
+
+impl<P: Params> HostRegistry<P> {
+    /// Creates every host of the config (and registers its aliases), failing with the name
+    /// of the first host failed.
+    #[cfg(feature = "pinger")]
+    pub fn from_config<B: Behaviour<Handling = P::Handling>>(
+        config: HostsConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Error>
+    where
+        B::ProcessError: Default,
+    {
+        Self::build(config, Host::new::<B>)
+    }
+
+    /// Creates every host of the config (and registers its aliases), failing with the name
+    /// of the first host failed.
+    #[cfg(not(feature = "pinger"))]
+    pub fn from_config(
+        config: HostsConfig<<P::Timeouts as TimeoutsParams>::Key>,
+    ) -> Result<Self, Error> {
+        Self::build(config, Host::new)
+    }
+
+    fn build<F>(
+        config: HostsConfig<<P::Timeouts as TimeoutsParams>::Key>,
+        create: F,
+    ) -> Result<Self, Error>
+    where
+        F: Fn(HostConfig<<P::Timeouts as TimeoutsParams>::Key>) -> Result<Host<P>, Error>,
+    {
+        let registry = Self::default();
+        for (alias, name) in config.aliases {
+            registry.alias(alias, name);
+        }
+        for (name, config) in config.hosts {
+            let host = create(config).map_err(|source| Error::NamedHost {
+                name: name.clone(),
+                source: Box::new(source),
+            })?;
+            registry.insert(name, host);
+        }
+        Ok(registry)
+    }
+
+    /// Registers the host under the given name, returning the one replaced (if any).
+    pub fn insert(&self, name: impl Into<String>, host: Host<P>) -> Option<Host<P>> {
+        self.hosts
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(name.into(), host)
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Host<P>> {
+        self.hosts
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(name)
+    }
+
+    /// Makes the host registered under the name to be found by the alias as well, returning
+    /// the name the alias referred to before (if any). Aliases are deprecated names: every
+    /// lookup by them is reported with [`Callbacks::on_alias_used`](super::Callbacks::on_alias_used)
+    /// (if the `callbacks` feature is on). Aliases of aliases are not followed, and the hosts
+    /// take precedence over the aliases of the same names.
+    pub fn alias(&self, alias: impl Into<String>, name: impl Into<String>) -> Option<String> {
+        self.aliases
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(alias.into(), name.into())
+    }
+
+    pub fn unalias(&self, alias: &str) -> Option<String> {
+        self.aliases
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(alias)
+    }
+
+    /// Looks the host up by its name or alias.
+    pub fn get(&self, name: &str) -> Option<Host<P>> {
+        let hosts = self
+            .hosts
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(host) = hosts.get(name) {
+            return Some(host.clone());
+        }
+        let aliases = self
+            .aliases
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let target = aliases.get(name)?;
+        #[cfg(feature = "callbacks")]
+        P::Callbacks::on_alias_used(name, target);
+        hosts.get(target).cloned()
+    }
+
+    /// Names of the hosts, without the aliases.
+    pub fn names(&self) -> Vec<String> {
+        self.hosts
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Aliases along with the names they refer to.
+    pub fn aliases(&self) -> BTreeMap<String, String> {
+        self.aliases
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(alias, name)| (alias.clone(), name.clone()))
+            .collect()
+    }
+
+    pub fn snapshot(&self) -> RegistrySnapshot {
+        RegistrySnapshot(
+            self.hosts
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .iter()
+                .map(|(name, host)| (name.clone(), host.health_report()))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,405 @@
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, ETAG},
+    Method, Request, RequestBuilder, Response, StatusCode, Url,
+};
+
+#[cfg(feature = "tls-pinning")]
+use super::SpkiPinMismatch;
+use super::{CodecError, Error};
+use crate::credentials;
+
+pub const XRI_HEADER: &str = "X-Request-Id";
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+// NOTE: the name is ready to use, so it is neither parsed nor allocated for every request
+const XRI_HEADER_NAME: HeaderName = HeaderName::from_static("x-request-id");
+
+/// `X-Request-Id` validated once, to be reused by all the requests made on behalf of the same
+/// incoming one (see [`Host::request_xri`](super::Host::request_xri)) instead of validating and
+/// copying the text into the header value for every request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Xri {
+    text: Arc<str>,
+    header: HeaderValue,
+}
+
+impl Xri {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn header(&self) -> &HeaderValue {
+        &self.header
+    }
+}
+
+impl TryFrom<Arc<str>> for Xri {
+    type Error = Error;
+
+    fn try_from(text: Arc<str>) -> Result<Self, Self::Error> {
+        // NOTE: the value must be readable as text as well, so only visible ASCII is allowed
+        match HeaderValue::from_str(&text) {
+            Ok(header) if header.to_str().is_ok() => Ok(Self { text, header }),
+            _ => Err(Error::InvalidXri(text.as_ref().into())),
+        }
+    }
+}
+
+impl TryFrom<&str> for Xri {
+    type Error = Error;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        Self::try_from(Arc::<str>::from(text))
+    }
+}
+
+impl TryFrom<HeaderValue> for Xri {
+    type Error = Error;
+
+    fn try_from(header: HeaderValue) -> Result<Self, Self::Error> {
+        let text = header
+            .to_str()
+            .map_err(|_| Error::InvalidXri(String::from_utf8_lossy(header.as_bytes()).into()))?
+            .into();
+        Ok(Self { text, header })
+    }
+}
+
+/// Request id as given to the host: either the text to put into the header, or the ready one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum XriRef<'a> {
+    Text(&'a str),
+    Ready(&'a Xri),
+}
+
+impl<'a> XriRef<'a> {
+    pub(crate) fn as_str(&self) -> &'a str {
+        match self {
+            Self::Text(text) => text,
+            Self::Ready(xri) => xri.as_str(),
+        }
+    }
+
+    pub(crate) fn apply(self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Self::Text(text) => request.header(XRI_HEADER_NAME, text),
+            Self::Ready(xri) => request.header(XRI_HEADER_NAME, xri.header.clone()),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for XriRef<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl<'a> From<&'a Xri> for XriRef<'a> {
+    fn from(xri: &'a Xri) -> Self {
+        Self::Ready(xri)
+    }
+}
+
+/// Method, path and `X-Request-Id` of the request, to be used in errors.
+pub(crate) fn request_origin(request: &Request) -> (Method, String, String) {
+    let xri = request
+        .headers()
+        .get(XRI_HEADER)
+        .and_then(|xri| xri.to_str().ok())
+        .unwrap_or_default();
+    (
+        request.method().clone(),
+        request.url().path().into(),
+        xri.into(),
+    )
+}
+
+/// Response with its body already received, so it can be cloned and shared.
+#[derive(Clone, Debug)]
+pub struct FullResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl FullResponse {
+    /// Version of the resource, to make the later requests conditional on
+    /// (see [`Host::send_if_match`](super::Host::send_if_match)).
+    pub fn etag(&self) -> Option<&HeaderValue> {
+        self.headers.get(ETAG)
+    }
+
+    pub async fn receive(response: Response) -> Result<Self, reqwest::Error> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Circumstances of the request sending, for SLO accounting and alike.
+#[derive(Clone, Debug)]
+pub struct SendReport {
+    pub xri: String,
+    /// Count of the requests really sent to the host: zero if the request was refused or failed
+    /// to be built, more than one if it was repeated with the refreshed credentials or to answer
+    /// the digest challenge.
+    pub attempts: u32,
+    /// URL the response was received from (after redirects if any).
+    pub url: Option<Url>,
+    /// Time spent on resolving the host name, if known (requires the `dns-timings` feature,
+    /// and is None for the requests made through pooled connections).
+    pub dns: Option<Duration>,
+    /// Time to the first byte (i.e. till the response headers received), if received.
+    pub ttfb: Option<Duration>,
+    /// Time till the response body is received or the request failed.
+    pub total: Duration,
+    /// Response headers listed in [`HostConfig::correlation_headers`](super::HostConfig::correlation_headers)
+    /// (like the request IDs echoed or assigned by the server), even for the negative response statuses.
+    pub correlation: HeaderMap,
+}
+
+/// Failure of a request made through the [`Host::send`](super::Host::send) family of methods,
+/// with the data of the originating request attached.
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
+#[error("Request {method} '{path}' with X-Request-Id '{xri}' failed: {kind}")]
+pub struct SendError {
+    pub method: Method,
+    pub path: String,
+    pub xri: String,
+    #[source]
+    pub kind: SendErrorKind,
+}
+
+impl SendError {
+    pub fn new(method: Method, path: &str, xri: &str, kind: SendErrorKind) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            xri: xri.into(),
+            kind,
+        }
+    }
+
+    #[inline]
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+
+    #[inline]
+    pub fn is_timeout(&self) -> bool {
+        self.kind.is_timeout()
+    }
+
+    /// Whether the conditional request failed because the resource has changed.
+    #[inline]
+    pub fn is_precondition_failed(&self) -> bool {
+        matches!(self.kind, SendErrorKind::PreconditionFailed(_))
+    }
+
+    /// Whether the host refused the credentials (401) or the access granted with them (403).
+    #[inline]
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(
+            self.status(),
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+        )
+    }
+
+    #[inline]
+    pub fn status(&self) -> Option<StatusCode> {
+        self.kind.status()
+    }
+
+    /// Certificate pinning failure of the connection, if that is why the request failed.
+    #[cfg(feature = "tls-pinning")]
+    #[inline]
+    pub fn pin_mismatch(&self) -> Option<&SpkiPinMismatch> {
+        self.kind.pin_mismatch()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SendErrorKind {
+    #[error("Failed building request: {0}")]
+    Build(#[source] reqwest::Error),
+    #[error("Failed authenticating request: {0}")]
+    Sign(#[source] credentials::Error),
+    #[error("Failed sending request: {0}")]
+    Request(#[source] reqwest::Error),
+    #[error("Negative response status {0}")]
+    Status(StatusCode),
+    #[error("Failed receiving response body: {0}")]
+    Body(#[source] reqwest::Error),
+    #[error("Failed encoding request body: {0}")]
+    Encode(#[source] CodecError),
+    #[error("Failed decoding response body: {0}")]
+    Decode(#[source] CodecError),
+    #[error("Negative answer: {0}")]
+    Rejected(String),
+    #[error("Shared request failed: {0}")]
+    Shared(Arc<SendError>),
+    #[error("Request refused: {0}")]
+    Denied(#[source] Error),
+    #[error("No data received within {0:?}")]
+    Stalled(Duration),
+    #[error("Precondition failed: the resource no longer matches ETag {0:?}")]
+    PreconditionFailed(HeaderValue),
+}
+
+impl SendErrorKind {
+    /// Whether repeating the very same request has a chance to succeed:
+    /// connection failures, timeouts, throttling and server-side errors are considered transient.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Build(_) | Self::Sign(_) => false,
+            #[cfg(feature = "tls-pinning")]
+            Self::Request(_) if self.pin_mismatch().is_some() => false,
+            Self::Request(source) => source.is_connect() || source.is_timeout(),
+            Self::Status(status) => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            Self::Body(source) => source.is_timeout(),
+            Self::Denied(source) => source.is_retryable(),
+            Self::Encode(_) | Self::Decode(_) | Self::Rejected(_) | Self::PreconditionFailed(_) => {
+                false
+            }
+            Self::Stalled(_) => true,
+            Self::Shared(source) => source.is_retryable(),
+        }
+    }
+
+    /// Whether the failure was caused by a timeout, either client-side or reported by the server.
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Self::Build(_)
+            | Self::Sign(_)
+            | Self::Encode(_)
+            | Self::Decode(_)
+            | Self::Rejected(_)
+            | Self::Denied(_)
+            | Self::PreconditionFailed(_) => false,
+            Self::Request(source) | Self::Body(source) => source.is_timeout(),
+            Self::Status(status) => {
+                *status == StatusCode::REQUEST_TIMEOUT || *status == StatusCode::GATEWAY_TIMEOUT
+            }
+            Self::Stalled(_) => true,
+            Self::Shared(source) => source.is_timeout(),
+        }
+    }
+
+    /// Response status, if the response was received at all.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Build(source) | Self::Request(source) | Self::Body(source) => source.status(),
+            Self::Status(status) => Some(*status),
+            Self::PreconditionFailed(_) => Some(StatusCode::PRECONDITION_FAILED),
+            Self::Sign(_)
+            | Self::Encode(_)
+            | Self::Decode(_)
+            | Self::Rejected(_)
+            | Self::Denied(_)
+            | Self::Stalled(_) => None,
+            Self::Shared(source) => source.status(),
+        }
+    }
+
+    /// Certificate pinning failure of the connection, if that is why the request failed.
+    #[cfg(feature = "tls-pinning")]
+    pub fn pin_mismatch(&self) -> Option<&SpkiPinMismatch> {
+        match self {
+            Self::Request(source) => SpkiPinMismatch::find(source),
+            Self::Shared(source) => source.pin_mismatch(),
+            _ => None,
+        }
+    }
+}
+
+/// How the failed request should be treated by the code repeating it, see [`Params::classify`](super::Params::classify).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Repeating may succeed.
+    Retryable,
+    /// Repeating makes no sense.
+    Fatal,
+    /// Upstream asks to slow down, so repeating should be postponed longer than usual.
+    Throttle,
+}
+
+impl ErrorClass {
+    /// Default classification: 429 and 503 statuses are throttling,
+    /// others follow [`SendErrorKind::is_retryable`].
+    pub fn of(error: &SendError) -> Self {
+        match error.status() {
+            Some(StatusCode::TOO_MANY_REQUESTS) | Some(StatusCode::SERVICE_UNAVAILABLE) => {
+                Self::Throttle
+            }
+            _ if error.is_retryable() => Self::Retryable,
+            _ => Self::Fatal,
+        }
+    }
+}
+
+/// Results of the batch in the same order the requests were given.
+#[derive(Debug)]
+pub struct BatchResults(pub Vec<Result<Response, SendError>>);
+
+impl BatchResults {
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &SendError)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|error| (index, error)))
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.0.iter().all(Result::is_ok)
+    }
+
+    /// Gives all the responses if every request succeeded, or all the errors otherwise.
+    pub fn into_result(self) -> Result<Vec<Response>, BatchError> {
+        let total = self.0.len();
+        let (responses, failed): (Vec<_>, Vec<_>) = self
+            .0
+            .into_iter()
+            .enumerate()
+            .partition(|(_, result)| result.is_ok());
+        if failed.is_empty() {
+            return Ok(responses
+                .into_iter()
+                .filter_map(|(_, result)| result.ok())
+                .collect());
+        }
+        Err(BatchError {
+            total,
+            failed: failed
+                .into_iter()
+                .filter_map(|(index, result)| result.err().map(|error| (index, error)))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{} of {total} batched requests failed{}", failed.len(), first_failure(failed))]
+pub struct BatchError {
+    pub total: usize,
+    /// Failed requests positions in the batch along with their errors, not empty if made by
+    /// [`BatchResults::into_result`].
+    pub failed: Vec<(usize, SendError)>,
+}
+
+fn first_failure(failed: &[(usize, SendError)]) -> String {
+    match failed.first() {
+        Some((index, error)) => format!(", first of them (#{}): {}", index, error),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::future::{self, BoxFuture};
+use tokio::task::JoinHandle;
+
+type Subscriber = Box<dyn Fn(Duration) -> BoxFuture<'static, usize> + Send + Sync>;
+
+#[derive(Default)]
+struct Subscribers {
+    hosts: Vec<Subscriber>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+/// Single point to stop everything spawned for the subscribed hosts (see [`Host::subscribe`](super::Host::subscribe))
+/// and the tracked tasks (like credentials watchers), e.g. on the service shutdown.
+/// Clones share the subscriptions.
+#[derive(Clone, Default)]
+pub struct Shutdown(Arc<Mutex<Subscribers>>);
+
+/// Outcome of the [`Shutdown::run`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Number of hosts drained and stopped.
+    pub hosts: usize,
+    /// Number of requests still in flight when the timeout elapsed, summed over the hosts.
+    pub inflight: usize,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn subscribe(&self, subscriber: Subscriber) {
+        self.lock().hosts.push(subscriber);
+    }
+
+    /// Makes the task aborted on shutdown, after all the hosts are stopped.
+    pub fn track(&self, task: JoinHandle<()>) {
+        self.lock().tasks.push(task);
+    }
+
+    /// Drains all the subscribed hosts at once (each one waiting up to the timeout for its requests
+    /// in flight), stops their pingers and aborts the tracked tasks. Everything is unsubscribed then,
+    /// so the following runs stop only the ones subscribed later.
+    pub async fn run(&self, timeout: Duration) -> ShutdownReport {
+        let Subscribers { hosts, tasks } = mem::take(&mut *self.lock());
+        let inflight = future::join_all(hosts.iter().map(|drain| drain(timeout))).await;
+        for task in tasks {
+            task.abort();
+        }
+        ShutdownReport {
+            hosts: hosts.len(),
+            inflight: inflight.into_iter().sum(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Subscribers> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
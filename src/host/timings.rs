@@ -0,0 +1,57 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+/// Latest name resolution of a host.
+#[derive(Clone, Copy, Debug)]
+struct Resolution {
+    started: Instant,
+    elapsed: Duration,
+}
+
+/// System resolver which remembers how long resolving every name took. Reqwest doesn't tell
+/// which request made the connection (and so the resolution), so resolutions are matched
+/// to requests by time: precise for sequential requests, approximate for concurrent ones.
+#[derive(Clone, Default)]
+pub(crate) struct TimingResolver(Arc<Mutex<HashMap<String, Resolution>>>);
+
+impl TimingResolver {
+    /// Duration of the resolution of the given name started after the given moment, if any.
+    pub(crate) fn resolved_since(&self, name: &str, since: Instant) -> Option<Duration> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .filter(|resolution| resolution.started >= since)
+            .map(|resolution| resolution.elapsed)
+    }
+}
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolutions = self.0.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            // NOTE: port is needed by the lookup only, reqwest replaces it with the actual one
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            resolutions
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(
+                    name.as_str().into(),
+                    Resolution {
+                        started,
+                        elapsed: started.elapsed(),
+                    },
+                );
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use reqwest::{Client, Request, Response};
+
+use super::SendErrorKind;
+
+/// Executor of the built requests, the reqwest client itself by default. Requests are still built
+/// with the reqwest client, so alternative transports (test mocks, other HTTP stacks, UDS connectors)
+/// take reqwest requests and give reqwest responses (which can be made from `http::Response`).
+/// Note that the pinger and keepalive requests are always sent with the reqwest client.
+#[async_trait]
+pub trait Transport: Send + Sync + 'static {
+    /// Makes the transport from the client built according to the host config.
+    fn from_client(client: Client) -> Self;
+
+    async fn execute(&self, request: Request) -> Result<Response, SendErrorKind>;
+}
+
+#[async_trait]
+impl Transport for Client {
+    fn from_client(client: Client) -> Self {
+        client
+    }
+
+    async fn execute(&self, request: Request) -> Result<Response, SendErrorKind> {
+        Client::execute(self, request)
+            .await
+            .map_err(SendErrorKind::Request)
+    }
+}
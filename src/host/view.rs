@@ -0,0 +1,157 @@
+use std::{borrow::Cow, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, IntoHeaderName},
+    Method, RequestBuilder, Response,
+};
+
+use super::{Host, Params, SendError, TimeoutsParams};
+
+/// Lightweight view of the host sharing its client, which adds the same headers,
+/// path prefix, timeout and request ID scope to every request built through it.
+/// Made by the `with_*` methods of [`Host`] and chained further the same way.
+pub struct HostView<P: Params> {
+    host: Host<P>,
+    headers: HeaderMap,
+    base_path: String,
+    timeout: Option<Duration>,
+    scope: Option<String>,
+}
+
+impl<P: Params> Host<P> {
+    /// Makes a view adding the given header to every request.
+    pub fn with_header<K: IntoHeaderName>(&self, name: K, value: HeaderValue) -> HostView<P> {
+        HostView::from(self.clone()).with_header(name, value)
+    }
+
+    /// Makes a view prefixing every request path with the given one (e.g. "/v2").
+    pub fn with_base_path(&self, base_path: &str) -> HostView<P> {
+        HostView::from(self.clone()).with_base_path(base_path)
+    }
+
+    /// Makes a view using the timeout of the given key for every request.
+    pub fn with_timeout_key(&self, spec: &<P::Timeouts as TimeoutsParams>::Key) -> HostView<P> {
+        HostView::from(self.clone()).with_timeout_key(spec)
+    }
+
+    /// Makes a view prefixing every request ID with the given label (as "label:xri"),
+    /// so the traffic of some subsystem can be told apart in callbacks, events and upstream logs.
+    pub fn scoped(&self, label: &str) -> HostView<P> {
+        HostView::from(self.clone()).scoped(label)
+    }
+}
+
+impl<P: Params> From<Host<P>> for HostView<P> {
+    fn from(host: Host<P>) -> Self {
+        Self {
+            host,
+            headers: HeaderMap::new(),
+            base_path: String::new(),
+            timeout: None,
+            scope: None,
+        }
+    }
+}
+
+impl<P: Params> HostView<P> {
+    /// Adds the header, replacing the one of the same name added before.
+    pub fn with_header<K: IntoHeaderName>(mut self, name: K, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Appends the given path to the prefix set before.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path.push_str(base_path.trim_end_matches('/'));
+        self
+    }
+
+    /// Replaces the timeout set before.
+    pub fn with_timeout_key(mut self, spec: &<P::Timeouts as TimeoutsParams>::Key) -> Self {
+        self.timeout = Some(self.host.0.capped(self.host.0.timeouts[spec]));
+        self
+    }
+
+    /// Nests the scope into the one set before (as "outer.inner").
+    pub fn scoped(mut self, label: &str) -> Self {
+        self.scope = Some(match self.scope.take() {
+            Some(outer) => format!("{}.{}", outer, label),
+            None => label.into(),
+        });
+        self
+    }
+
+    pub fn scope(&self) -> Option<&str> {
+        self.scope.as_deref()
+    }
+
+    pub fn host(&self) -> &Host<P> {
+        &self.host
+    }
+
+    fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = request.headers(self.headers.clone());
+        match self.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    fn path(&self, path: &str) -> String {
+        format!("{}{}", self.base_path, path)
+    }
+
+    fn xri<'a>(&self, xri: &'a str) -> Cow<'a, str> {
+        match &self.scope {
+            Some(scope) => format!("{}:{}", scope, xri).into(),
+            None => xri.into(),
+        }
+    }
+
+    pub fn request(&self, method: Method, path: &str, xri: &str) -> RequestBuilder {
+        self.apply(
+            self.host
+                .request(method, &self.path(path), None, &self.xri(xri)),
+        )
+    }
+
+    #[inline]
+    pub fn get(&self, path: &str, xri: &str) -> RequestBuilder {
+        self.request(Method::GET, path, xri)
+    }
+
+    #[inline]
+    pub fn post(&self, path: &str, xri: &str) -> RequestBuilder {
+        self.request(Method::POST, path, xri)
+    }
+
+    /// Same as [`Host::send`] with the view settings applied.
+    pub async fn send<F>(
+        &self,
+        method: Method,
+        path: &str,
+        xri: &str,
+        prepare: F,
+    ) -> Result<Response, SendError>
+    where
+        F: FnOnce(RequestBuilder) -> RequestBuilder,
+    {
+        self.host
+            .send(method, &self.path(path), None, &self.xri(xri), |request| {
+                prepare(self.apply(request))
+            })
+            .await
+    }
+}
+
+impl<P: Params> Clone for HostView<P> {
+    fn clone(&self) -> Self {
+        Self {
+            host: self.host.clone(),
+            headers: self.headers.clone(),
+            base_path: self.base_path.clone(),
+            timeout: self.timeout,
+            scope: self.scope.clone(),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    error::Error as StdError,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use futures::FutureExt;
+use tokio::task::JoinHandle;
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+use super::IdentityConfig;
+use super::{store_credentials, Error, Host, Params};
+use crate::credentials::{Credentials, CredentialsProvider};
+
+/// Keeps the host credentials in sync with the secrets file (JSON object with `name` and `key`
+/// fields), checking its modification time periodically.
+#[derive(Clone, Debug)]
+pub struct CredentialsWatcher {
+    path: PathBuf,
+    period: Duration,
+}
+
+impl CredentialsWatcher {
+    pub fn new<T: Into<PathBuf>>(path: T, period: Duration) -> Self {
+        Self {
+            path: path.into(),
+            period,
+        }
+    }
+
+    /// Applies the credentials from the file at once, then spawns the task re-applying them
+    /// every time the file is modified. Failed re-reads are repeated on the next check, keeping
+    /// the previous credentials meanwhile. The task ends along with the host.
+    pub async fn spawn<P: Params>(self, host: &Host<P>) -> Result<JoinHandle<()>, WatcherError> {
+        let (mut modified, credentials) = read(&self.path).await?;
+        host.set_credentials(credentials)
+            .map_err(WatcherError::Apply)?;
+        // NOTE: only the credentials are referred, so the task doesn't prolong the host life
+        let slot = Arc::downgrade(&host.0.credentials);
+
+        Ok(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(self.period).await;
+                if slot.strong_count() == 0 {
+                    break;
+                }
+                match modification_time(&self.path).await {
+                    Ok(current) if current != modified => (),
+                    _ => continue,
+                }
+                let (current, credentials) = match read(&self.path).await {
+                    Ok(read) => read,
+                    Err(_) => continue,
+                };
+                let slot = match slot.upgrade() {
+                    Some(slot) => slot,
+                    None => break,
+                };
+                if store_credentials(&slot, credentials).is_ok() {
+                    modified = current;
+                }
+            }
+        }))
+    }
+}
+
+/// Applies the credentials given by the provider at once, then spawns the task fetching and
+/// applying them again when two thirds of their lease pass. Failed fetches are repeated after
+/// the `retry` period. The task ends along with the host, or once the credentials never expire.
+/// The provider is also asked to refresh the credentials once the host rejects them,
+/// if [`HostConfig::refresh_on_unauthorized`](super::HostConfig::refresh_on_unauthorized) is set.
+pub async fn spawn_provider<P, R>(
+    host: &Host<P>,
+    provider: R,
+    retry: Duration,
+) -> Result<JoinHandle<()>, WatcherError>
+where
+    P: Params,
+    R: CredentialsProvider + 'static,
+{
+    let lease = provider
+        .fetch()
+        .await
+        .map_err(|source| WatcherError::Fetch(Box::new(source)))?;
+    host.0.credentials.store(Arc::new(lease.headers));
+    let provider = Arc::new(provider);
+    let refresher = provider.clone();
+    host.0.refresher.store(Some(Arc::new(Box::new(move || {
+        let provider = refresher.clone();
+        async move { provider.refresh().await.ok().map(|lease| lease.headers) }.boxed()
+    }))));
+    let slot = Arc::downgrade(&host.0.credentials);
+    let mut delay = renewal(lease.duration);
+
+    Ok(tokio::spawn(async move {
+        while let Some(current) = delay {
+            tokio::time::sleep(current).await;
+            if slot.strong_count() == 0 {
+                break;
+            }
+            let lease = match provider.fetch().await {
+                Ok(lease) => lease,
+                Err(_) => {
+                    delay = Some(retry);
+                    continue;
+                }
+            };
+            match slot.upgrade() {
+                Some(slot) => slot.store(Arc::new(lease.headers)),
+                None => break,
+            };
+            delay = renewal(lease.duration);
+        }
+    }))
+}
+
+/// Keeps the host client identity in sync with its files (see [`Host::reload_identity`]), checking
+/// their modification times periodically. The new client is built aside and swapped in once ready,
+/// so the requests never wait for it.
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+#[derive(Clone, Debug)]
+pub struct IdentityWatcher {
+    period: Duration,
+}
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+impl IdentityWatcher {
+    pub fn new(period: Duration) -> Self {
+        Self { period }
+    }
+
+    /// Spawns the task reloading the identity every time any of its files is modified. Failed
+    /// reloads are repeated on the next check, keeping the previous client meanwhile. The task
+    /// ends on the first check after all the other handles of the host are dropped.
+    pub async fn spawn<P: Params>(self, host: &Host<P>) -> Result<JoinHandle<()>, WatcherError>
+    where
+        Host<P>: Send + Sync + 'static,
+    {
+        let identity = host
+            .0
+            .rebuild
+            .as_ref()
+            .and_then(|config| config.identity.clone())
+            .ok_or(WatcherError::NoIdentity)?;
+        let mut modified = identity_modified(&identity).await?;
+        // NOTE: the host itself is needed to rebuild its client, so the task keeps it alive while
+        // anybody else does
+        let host = host.clone();
+
+        Ok(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(self.period).await;
+                if Arc::strong_count(&host.0) == 1 {
+                    break;
+                }
+                // NOTE: the files replaced one by one may be caught halfway, then the second
+                // one is caught on the next check
+                let current = match identity_modified(&identity).await {
+                    Ok(current) if current != modified => current,
+                    _ => continue,
+                };
+                // NOTE: building the client reads the files, which is not for the runtime threads
+                let reloading = host.clone();
+                let reloaded =
+                    tokio::task::spawn_blocking(move || reloading.reload_identity()).await;
+                if let Ok(Ok(_)) = reloaded {
+                    modified = current;
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(any(
+    feature = "tls-native",
+    feature = "tls-rustls",
+    feature = "tls-rustls-webpki"
+))]
+async fn identity_modified(
+    identity: &IdentityConfig,
+) -> Result<(SystemTime, SystemTime), WatcherError> {
+    let modified = |path: &Path| {
+        let path = path.to_owned();
+        async move {
+            modification_time(&path)
+                .await
+                .map_err(|source| WatcherError::ReadIdentity { path, source })
+        }
+    };
+    Ok((
+        modified(&identity.cert).await?,
+        modified(&identity.key).await?,
+    ))
+}
+
+fn renewal(lease: Option<Duration>) -> Option<Duration> {
+    lease.map(|duration| duration * 2 / 3)
+}
+
+async fn modification_time(path: &Path) -> io::Result<SystemTime> {
+    tokio::fs::metadata(path).await?.modified()
+}
+
+async fn read(path: &Path) -> Result<(SystemTime, Credentials), WatcherError> {
+    let fail = |source| WatcherError::Read {
+        path: path.to_owned(),
+        source,
+    };
+    let modified = modification_time(path).await.map_err(fail)?;
+    let text = tokio::fs::read(path).await.map_err(fail)?;
+    let credentials = serde_json::from_slice(&text).map_err(WatcherError::Parse)?;
+    Ok((modified, credentials))
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because std::io::Error doesn't implement it
+pub enum WatcherError {
+    #[error("Failed reading credentials file '{}': {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[error("Failed reading identity file '{}': {source}", path.display())]
+    ReadIdentity { path: PathBuf, source: io::Error },
+    #[cfg(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))]
+    #[error("Host has no reloadable identity")]
+    NoIdentity,
+    #[error("Failed parsing credentials: {0}")]
+    Parse(#[source] serde_json::Error),
+    #[error("Failed fetching credentials: {0}")]
+    Fetch(#[source] Box<dyn StdError + Send + Sync>),
+    #[error("Failed applying credentials: {0}")]
+    Apply(#[source] Error),
+}
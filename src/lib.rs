@@ -1,27 +1,52 @@
+#[cfg(all(
+    feature = "tls-expiry",
+    not(any(
+        feature = "tls-native",
+        feature = "tls-rustls",
+        feature = "tls-rustls-webpki"
+    ))
+))]
+compile_error!("Feature \"tls-expiry\" requires any of the TLS features to be enabled");
+
+#[cfg(all(
+    feature = "tls-pinning",
+    not(any(feature = "tls-rustls", feature = "tls-rustls-webpki"))
+))]
+compile_error!("Feature \"tls-pinning\" requires any of the rustls features to be enabled");
+
 pub mod address;
 pub mod credentials;
+pub mod error;
 pub mod host;
 #[cfg(feature = "pinger")]
 pub mod ping;
+pub mod secret;
+#[cfg(feature = "test-server")]
+pub mod test_server;
 pub mod timeoutsmap;
 
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 pub use reqwest;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+pub use self::error::{Error, ErrorKind};
 pub use self::host::*;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Scheme {
     Http,
+    #[default]
     Https,
+    Ws,
+    Wss,
 }
 
-impl Default for Scheme {
-    fn default() -> Self {
-        Self::Https
+impl Scheme {
+    /// Whether the scheme implies TLS-protected connections.
+    pub fn is_secure(self) -> bool {
+        matches!(self, Self::Https | Self::Wss)
     }
 }
 
@@ -30,6 +55,8 @@ impl From<Scheme> for &str {
         match src {
             Scheme::Http => "http",
             Scheme::Https => "https",
+            Scheme::Ws => "ws",
+            Scheme::Wss => "wss",
         }
     }
 }
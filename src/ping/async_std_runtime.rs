@@ -0,0 +1,113 @@
+use std::{future::Future, time::Duration};
+
+use super::{AbortableHandle, Finish, Handle, Handling, Sleep};
+
+/// Sleeping with the async-std timer.
+pub struct AsyncStdSleep;
+
+#[async_trait::async_trait]
+impl Sleep for AsyncStdSleep {
+    async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await
+    }
+}
+
+/// Spawning the pinger as an async-std task. Requests are driven by the tokio runtime
+/// which async-std provides with its `tokio1` feature.
+pub struct AsyncStdHandling;
+
+impl Handling for AsyncStdHandling {
+    type Handle = AbortableHandle;
+
+    fn spawn<Fut>(f: Fut, finish: Finish) -> Self::Handle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (f, handle) = AbortableHandle::wrap(f, finish);
+        // NOTE: dropping the join handle detaches the task
+        async_std::task::spawn(f);
+        handle
+    }
+
+    fn stop(handle: Self::Handle) {
+        handle.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::Client;
+
+    use super::*;
+    use crate::{
+        host::Json,
+        ping::{pinger, Behaviour, DontProcessError, EmptyAnswer, EmptyQuestion, Probe, Settings},
+    };
+
+    struct AsyncStdBehaviour;
+
+    impl Behaviour for AsyncStdBehaviour {
+        type Question = EmptyQuestion;
+        type Answer = EmptyAnswer;
+        type Codec = Json;
+        type Sleep = AsyncStdSleep;
+        type ProcessError = DontProcessError<std::convert::Infallible>;
+        type Handling = AsyncStdHandling;
+    }
+
+    fn spawn_pinger() -> AbortableHandle {
+        let probes = vec![Probe {
+            request: Client::new().get("http://127.0.0.1:9/health"),
+            mode: Default::default(),
+            path: "/health".into(),
+            weight: 1,
+        }];
+        let settings = Settings {
+            period: Duration::from_secs(3600),
+            max_period: Duration::from_secs(3600),
+            aggregation: Default::default(),
+            address: "127.0.0.1:9".into(),
+        };
+        #[cfg(feature = "callbacks")]
+        return pinger::<AsyncStdBehaviour, crate::host::TrivialCallbacks>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            futures::channel::oneshot::channel().0,
+            #[cfg(feature = "events")]
+            None,
+        );
+        #[cfg(not(feature = "callbacks"))]
+        return pinger::<AsyncStdBehaviour>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            futures::channel::oneshot::channel().0,
+            #[cfg(feature = "events")]
+            None,
+        );
+    }
+
+    #[async_std::test]
+    async fn finish_interrupts_sleep() {
+        let handle = spawn_pinger();
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        handle.finish();
+        async_std::future::timeout(Duration::from_secs(5), handle.stopped())
+            .await
+            .expect("Pinger should finish without waiting for the whole period");
+    }
+
+    #[async_std::test]
+    async fn abort_stops_at_once() {
+        let handle = spawn_pinger();
+        handle.abort();
+        async_std::future::timeout(Duration::from_secs(5), handle.stopped())
+            .await
+            .expect("Pinger should be aborted at once");
+    }
+}
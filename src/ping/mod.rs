@@ -0,0 +1,1012 @@
+#[cfg(feature = "async-std")]
+pub mod async_std_runtime;
+#[cfg(feature = "smol")]
+pub mod smol_runtime;
+#[cfg(feature = "tokio")]
+pub mod tokio_runtime;
+
+use reqwest::{
+    header::{HeaderMap, ACCEPT, CONTENT_TYPE},
+    Method, RequestBuilder, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    future::Future,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::Poll,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{channel::oneshot, future, task::AtomicWaker};
+
+#[cfg(feature = "callbacks")]
+use crate::host::Callbacks;
+#[cfg(feature = "events")]
+use crate::host::{events::emit, EventSender, HostEvent};
+use crate::{
+    host::{Codec, CodecError, Decode, Encode, Json, Text, XRI_HEADER},
+    Scheme,
+};
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Config {
+    #[serde(with = "humantime_serde", default = "Config::def_period")]
+    pub period: Duration,
+    pub path: String,
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "Config::def_method")]
+    pub method: Method,
+    /// Scheme to use for ping requests instead of the host-wide one (e.g. plain HTTP health endpoint).
+    /// Plain HTTP is only sent by the host not strict about its scheme, see
+    /// [`HostConfig::strict_scheme`](crate::host::HostConfig::strict_scheme).
+    #[serde(default)]
+    pub scheme: Option<Scheme>,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Deadline of every ping request, half of the period if not set. Can't exceed the period.
+    #[serde(with = "humantime_serde", default)]
+    pub timeout: Option<Duration>,
+    /// Limit of the period growing after every failed ping (it's reset by the first successful one),
+    /// eight periods if not set. Can't be less than the period.
+    #[serde(with = "humantime_serde", default)]
+    pub max_period: Option<Duration>,
+    /// Share of the main probe (`path`, `method` and `mode` above) in [`Aggregation::Weighted`].
+    #[serde(default = "ProbeConfig::def_weight")]
+    pub weight: u32,
+    /// More probes made along with the main one on every ping (e.g. readiness along with liveness).
+    #[serde(default)]
+    pub probes: Vec<ProbeConfig>,
+    /// How the results of all the probes make the host health.
+    #[serde(default)]
+    pub aggregation: Aggregation,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProbeConfig {
+    pub path: String,
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "Config::def_method")]
+    pub method: Method,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Share of the probe in [`Aggregation::Weighted`].
+    #[serde(default = "ProbeConfig::def_weight")]
+    pub weight: u32,
+}
+
+impl ProbeConfig {
+    pub fn def_weight() -> u32 {
+        1
+    }
+}
+
+/// Rule making the host health of the results of several probes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    /// Host is up if all the probes passed.
+    #[default]
+    All,
+    /// Host is up if any of the probes passed.
+    Any,
+    /// Host is up if the passed probes have at least the given share (from 0 to 1) of the total weight.
+    Weighted(f64),
+}
+
+impl Aggregation {
+    /// Gives if the host is up by the weights of the probes and if they passed.
+    pub fn passed<I: IntoIterator<Item = (u32, bool)>>(self, results: I) -> bool {
+        let mut results = results.into_iter();
+        match self {
+            Self::All => results.all(|(_, passed)| passed),
+            Self::Any => results.any(|(_, passed)| passed),
+            Self::Weighted(share) => {
+                let (passed, total) =
+                    results.fold((0u64, 0u64), |(passed, total), (weight, ok)| {
+                        let weight = u64::from(weight);
+                        (passed + if ok { weight } else { 0 }, total + weight)
+                    });
+                passed as f64 >= share * total as f64
+            }
+        }
+    }
+}
+
+/// What the ping request is and how its response is evaluated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Request with the configured method, response status and [`Answer`] are evaluated.
+    #[default]
+    Answer,
+    /// HEAD request, only response status is evaluated.
+    Head,
+    /// Request with the configured method asking for the first byte only (`Range: bytes=0-0`),
+    /// only response status is evaluated and the body is not read.
+    Range,
+}
+
+impl Mode {
+    pub fn is_status_only(self) -> bool {
+        self != Self::Answer
+    }
+}
+
+impl Config {
+    pub fn def_period() -> Duration {
+        Duration::from_secs(4)
+    }
+
+    pub fn def_method() -> Method {
+        Method::GET
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(self.period / 2)
+    }
+
+    pub fn max_period(&self) -> Duration {
+        self.max_period.unwrap_or(self.period * 8)
+    }
+}
+
+/// Lightweight requests keeping pooled connections alive, separate from the health ping.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+pub struct KeepaliveConfig {
+    /// Should be just under the idle timeout of the host (or load balancer in front of it).
+    #[serde(with = "humantime_serde")]
+    pub period: Duration,
+    #[serde(default = "KeepaliveConfig::def_path")]
+    pub path: String,
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "KeepaliveConfig::def_method")]
+    pub method: Method,
+}
+
+impl KeepaliveConfig {
+    pub fn def_path() -> String {
+        "/".into()
+    }
+
+    pub fn def_method() -> Method {
+        Method::HEAD
+    }
+}
+
+pub trait Question: Sized {
+    fn ask() -> Option<Self>;
+}
+
+#[derive(Serialize)]
+pub struct EmptyQuestion;
+
+impl Question for EmptyQuestion {
+    fn ask() -> Option<Self> {
+        None
+    }
+}
+
+/// Ping response head and timing, given to [`Answer::evaluate`] along with the decoded body.
+#[derive(Clone, Debug)]
+pub struct ResponseInfo<'a> {
+    pub status: StatusCode,
+    pub headers: &'a HeaderMap,
+    /// Time passed from sending the ping till its body is received.
+    pub latency: Duration,
+}
+
+/// How the ping response body is turned into the [`Answer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyMode {
+    /// Decoded with the codec of the [`Behaviour`].
+    Decode,
+    /// Taken as UTF-8 text and given to [`Answer::from_text`].
+    Text,
+    /// Received and dropped, [`Answer::from_text`] is given an empty text.
+    Ignore,
+}
+
+pub trait Answer: Sized {
+    type Fail: Display + Send;
+
+    const BODY_MODE: BodyMode = BodyMode::Decode;
+
+    fn positivness(self) -> Result<(), Self::Fail>;
+
+    /// Makes the answer of the body in [`BodyMode::Text`] and [`BodyMode::Ignore`] modes.
+    fn from_text(_text: String) -> Result<Self, CodecError> {
+        Err("ping answer can't be made of text".into())
+    }
+
+    /// Checks the whole response (e.g. version headers, clock skew or latency budget).
+    /// Checks just the body by default; the status is checked by the pinger anyway.
+    fn evaluate(self, _info: &ResponseInfo) -> Result<(), Self::Fail> {
+        self.positivness()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EmptyAnswer;
+
+impl Answer for EmptyAnswer {
+    type Fail = Infallible;
+
+    const BODY_MODE: BodyMode = BodyMode::Ignore;
+
+    fn positivness(self) -> Result<(), Self::Fail> {
+        Ok(())
+    }
+
+    fn from_text(_text: String) -> Result<Self, CodecError> {
+        Ok(Self)
+    }
+}
+
+/// Plain text answer (e.g. "OK"), which is positive whatever the text is,
+/// so the response status decides.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct TextAnswer(pub String);
+
+impl Answer for TextAnswer {
+    type Fail = Infallible;
+
+    const BODY_MODE: BodyMode = BodyMode::Text;
+
+    fn positivness(self) -> Result<(), Self::Fail> {
+        Ok(())
+    }
+
+    fn from_text(text: String) -> Result<Self, CodecError> {
+        Ok(Self(text))
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Sleep {
+    async fn sleep(duration: Duration);
+}
+
+pub struct DontSleep;
+
+#[async_trait::async_trait]
+impl Sleep for DontSleep {
+    async fn sleep(_duration: Duration) {}
+}
+
+/// Circumstances of the ping attempt, passed to the [`ProcessError`] handler.
+#[derive(Clone, Debug)]
+pub struct Context<'a> {
+    /// Pinged host address (host and port).
+    pub address: &'a str,
+    pub path: &'a str,
+    /// Number of the ping attempt since the pinger start, beginning with 1.
+    pub attempt: u64,
+    /// Request ID sent with the ping, to find it in the host logs.
+    pub xri: &'a str,
+    /// Time passed since the attempt start.
+    pub elapsed: Duration,
+}
+
+pub trait ProcessError<R: Display> {
+    fn process_ping_error(&self, context: &Context, error: Error<R>);
+    fn process_request_clone_fail(&self, context: &Context);
+}
+
+pub struct DontProcessError<R: Display>(PhantomData<fn() -> R>);
+
+impl<R: Display> Default for DontProcessError<R> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<R: Display> ProcessError<R> for DontProcessError<R> {
+    fn process_ping_error(&self, _context: &Context, _error: Error<R>) {}
+    fn process_request_clone_fail(&self, _context: &Context) {}
+}
+
+/// Switch to ask the pinger to finish gracefully: the in-flight ping (if any) is completed,
+/// but no more pings are made.
+#[derive(Clone, Default)]
+pub struct Finish(Arc<FinishInner>);
+
+#[derive(Default)]
+struct FinishInner {
+    requested: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Finish {
+    pub fn request(&self) {
+        self.0.requested.store(true, Ordering::Release);
+        self.0.waker.wake();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.requested.load(Ordering::Acquire)
+    }
+
+    /// Resolves once the finish is requested. Only one waiter at a time is supported.
+    pub async fn requested(&self) {
+        future::poll_fn(|cx| {
+            self.0.waker.register(cx.waker());
+            if self.is_requested() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Control over the spawned pinger.
+#[async_trait::async_trait]
+pub trait Handle {
+    /// Cancels the pinger immediately, even in the middle of the ping.
+    fn abort(&self);
+
+    /// Asks the pinger to complete the in-flight ping (if any) and then finish.
+    fn finish(&self);
+
+    /// Resolves once the pinger is finished or aborted.
+    async fn stopped(&self);
+}
+
+#[async_trait::async_trait]
+impl Handle for () {
+    fn abort(&self) {}
+
+    fn finish(&self) {}
+
+    async fn stopped(&self) {}
+}
+
+pub trait Handling {
+    type Handle: Handle;
+
+    /// Spawns the pinger future, which finishes gracefully once `finish` is requested.
+    fn spawn<Fut>(f: Fut, finish: Finish) -> Self::Handle
+    where
+        Fut: Future<Output = ()> + Send + 'static;
+
+    /// Stops the pinger being no more needed (e.g. when the Host is dropped).
+    fn stop(handle: Self::Handle);
+}
+
+/// Handle of the pinger spawned as a detached abortable future, for runtimes which tasks
+/// can't be aborted through a shared reference.
+#[cfg(any(feature = "async-std", feature = "smol"))]
+pub struct AbortableHandle {
+    abort: future::AbortHandle,
+    finish: Finish,
+    done: Finish,
+}
+
+#[cfg(any(feature = "async-std", feature = "smol"))]
+impl AbortableHandle {
+    /// Makes the future to be spawned instead of the given one along with its handle.
+    pub fn wrap<Fut>(f: Fut, finish: Finish) -> (impl Future<Output = ()> + Send, Self)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (f, abort) = future::abortable(f);
+        let done = Finish::default();
+        let done_clone = done.clone();
+        let wrapped = async move {
+            let _ = f.await;
+            done_clone.request();
+        };
+        (
+            wrapped,
+            Self {
+                abort,
+                finish,
+                done,
+            },
+        )
+    }
+}
+
+#[cfg(any(feature = "async-std", feature = "smol"))]
+#[async_trait::async_trait]
+impl Handle for AbortableHandle {
+    fn abort(&self) {
+        self.abort.abort()
+    }
+
+    fn finish(&self) {
+        self.finish.request()
+    }
+
+    async fn stopped(&self) {
+        self.done.requested().await
+    }
+}
+
+pub struct NoHandling;
+
+impl Handling for NoHandling {
+    type Handle = ();
+
+    fn spawn<Fut>(_: Fut, _: Finish) -> Self::Handle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+    }
+
+    fn stop(_: Self::Handle) {}
+}
+
+pub trait Behaviour: 'static {
+    type Question: Question;
+    type Answer: Answer;
+    /// Format of ping request and response bodies.
+    type Codec: Encode<Self::Question> + Decode<Self::Answer>;
+    type Sleep: Sleep;
+    type ProcessError: ProcessError<<<Self as Behaviour>::Answer as Answer>::Fail> + Send;
+    type Handling: Handling;
+}
+
+pub struct MinimalBehaviour;
+
+impl Behaviour for MinimalBehaviour {
+    type Question = EmptyQuestion;
+    type Answer = EmptyAnswer;
+    type Codec = Json;
+    type Sleep = DontSleep;
+    type ProcessError = DontProcessError<<EmptyAnswer as Answer>::Fail>;
+    type Handling = NoHandling;
+}
+
+async fn ping_once<Q, A, C>(mut request: RequestBuilder, mode: Mode) -> Result<(), Error<A::Fail>>
+where
+    Q: Question,
+    A: Answer,
+    C: Encode<Q> + Decode<A>,
+{
+    if mode.is_status_only() {
+        // NOTE: the response is dropped with its body unread
+        let status = request.send().await.map_err(Error::Request)?.status();
+        return match status.is_success() {
+            true => Ok(()),
+            false => Err(Error::NegativeStatus(status)),
+        };
+    }
+    let accept = match A::BODY_MODE {
+        BodyMode::Decode => C::CONTENT_TYPE,
+        BodyMode::Text => Text::CONTENT_TYPE,
+        BodyMode::Ignore => "*/*",
+    };
+    request = request.header(ACCEPT, accept);
+    if let Some(question) = Q::ask() {
+        request = request
+            .header(CONTENT_TYPE, C::CONTENT_TYPE)
+            .body(C::encode(&question).map_err(Error::Encode)?);
+    };
+    let started = Instant::now();
+    let response = request.send().await.map_err(Error::Request)?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await.map_err(Error::Response)?;
+    let info = ResponseInfo {
+        status,
+        headers: &headers,
+        latency: started.elapsed(),
+    };
+    let answer = match A::BODY_MODE {
+        BodyMode::Decode => C::decode(&body),
+        BodyMode::Text => Text::decode(&body).and_then(A::from_text),
+        BodyMode::Ignore => A::from_text(String::new()),
+    };
+    let positivness_result = answer.map_err(Error::Decode)?.evaluate(&info);
+    match (status.is_success(), positivness_result) {
+        (_, Err(result)) => Err(Error::NegativeResult { status, result }),
+        (false, Ok(_)) => Err(Error::NegativeStatus(status)),
+        (true, Ok(_)) => Ok(()),
+    }
+}
+
+/// Host health as seen by the pinger.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    /// No ping has finished yet.
+    #[default]
+    Unknown,
+    Up,
+    Down,
+}
+
+/// Latest pinger observations, shared with the host to report its health.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PingStatus {
+    pub health: Health,
+    /// Duration of the last successful ping.
+    #[serde(with = "humantime_serde")]
+    pub last_latency: Option<Duration>,
+    pub consecutive_failures: u32,
+}
+
+/// Pinger settings not depending on its [`Behaviour`].
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub period: Duration,
+    pub max_period: Duration,
+    pub aggregation: Aggregation,
+    /// Pinged host address (host and port), for the error reports.
+    pub address: String,
+}
+
+/// Request made on every ping along with the other probes.
+#[derive(Debug)]
+pub struct Probe {
+    pub request: RequestBuilder,
+    pub mode: Mode,
+    pub path: String,
+    pub weight: u32,
+}
+
+pub fn pinger<B: Behaviour, #[cfg(feature = "callbacks")] C: Callbacks>(
+    probes: Vec<Probe>,
+    settings: Settings,
+    process_error: B::ProcessError,
+    status: Arc<Mutex<PingStatus>>,
+    first_result: oneshot::Sender<Health>,
+    #[cfg(feature = "events")] events: Option<EventSender>,
+) -> <<B as Behaviour>::Handling as Handling>::Handle {
+    let finish = Finish::default();
+    let finish_clone = finish.clone();
+    let Settings {
+        period,
+        max_period,
+        aggregation,
+        address,
+    } = settings;
+    let mut first_result = Some(first_result);
+    let pinger = async move {
+        let mut current_period = period;
+        let mut attempt = 0u64;
+        let (mut health, mut consecutive_fails) = (Health::Unknown, 0u32);
+        let mut last_latency = None;
+        let xri_base = ping_xri_base();
+        while !finish.is_requested() {
+            attempt += 1;
+            let started = Instant::now();
+            let xri = format!("{}-{}", xri_base, attempt);
+            let context = |path| Context {
+                address: &address,
+                path,
+                attempt,
+                xri: &xri,
+                elapsed: started.elapsed(),
+            };
+            let mut requests = Vec::with_capacity(probes.len());
+            for probe in &probes {
+                match probe.request.try_clone() {
+                    None => process_error.process_request_clone_fail(&context(&probe.path)),
+                    Some(request) => {
+                        requests.push((request.header(XRI_HEADER, &xri), probe.mode, probe.weight))
+                    }
+                }
+            }
+            if requests.len() < probes.len() {
+                sleep_unless_finished::<B::Sleep>(period, &finish).await;
+                continue;
+            }
+            #[cfg(feature = "callbacks")]
+            C::on_ping_started();
+            let (passed, results) = probe_all::<B>(requests, aggregation).await;
+            let failures: Vec<_> = probes
+                .iter()
+                .zip(results)
+                .filter_map(|(probe, result)| Some((probe, result.err()?)))
+                .collect();
+            if passed {
+                consecutive_fails = 0;
+                last_latency = Some(started.elapsed());
+                #[cfg(feature = "callbacks")]
+                C::on_ping_succeeded(started.elapsed());
+                #[cfg(feature = "events")]
+                emit(
+                    &events,
+                    HostEvent::PingSucceeded {
+                        latency: started.elapsed(),
+                    },
+                );
+                #[allow(unused_variables)]
+                if let Some(old) = change_health(&mut health, Health::Up) {
+                    #[cfg(feature = "callbacks")]
+                    C::on_health_changed(old, Health::Up);
+                    #[cfg(feature = "events")]
+                    emit(
+                        &events,
+                        HostEvent::HealthChanged {
+                            old,
+                            new: Health::Up,
+                        },
+                    );
+                }
+                current_period = period;
+            } else {
+                consecutive_fails += 1;
+                #[allow(unused_variables)]
+                let failure = describe_failures(&failures, probes.len());
+                #[cfg(feature = "callbacks")]
+                C::on_ping_failed(&failure, consecutive_fails, &xri);
+                #[cfg(feature = "events")]
+                emit(
+                    &events,
+                    HostEvent::PingFailed {
+                        error: failure,
+                        consecutive: consecutive_fails,
+                        xri: xri.clone(),
+                    },
+                );
+                #[allow(unused_variables)]
+                if let Some(old) = change_health(&mut health, Health::Down) {
+                    #[cfg(feature = "callbacks")]
+                    C::on_health_changed(old, Health::Down);
+                    #[cfg(feature = "events")]
+                    emit(
+                        &events,
+                        HostEvent::HealthChanged {
+                            old,
+                            new: Health::Down,
+                        },
+                    );
+                }
+                current_period = grown_period(current_period, period, max_period);
+            }
+            // NOTE: failed probes are reported even if the host is considered up
+            for (probe, ping_error) in failures {
+                process_error.process_ping_error(&context(&probe.path), ping_error);
+            }
+            *status
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = PingStatus {
+                health,
+                last_latency,
+                consecutive_failures: consecutive_fails,
+            };
+            if let Some(first_result) = first_result.take() {
+                let _ = first_result.send(health);
+            }
+            // NOTE: probes never overlap as the next one starts only after this one is finished,
+            // so the overran one is followed by the next one at once
+            let elapsed = started.elapsed();
+            let pause = match pause_after(current_period, elapsed) {
+                Some(pause) => pause,
+                None => {
+                    #[cfg(feature = "callbacks")]
+                    C::on_ping_overran(elapsed, current_period);
+                    #[cfg(feature = "events")]
+                    emit(
+                        &events,
+                        HostEvent::PingOverran {
+                            elapsed,
+                            period: current_period,
+                        },
+                    );
+                    Duration::ZERO
+                }
+            };
+            sleep_unless_finished::<B::Sleep>(pause, &finish).await;
+        }
+    };
+    B::Handling::spawn(pinger, finish_clone)
+}
+
+/// Makes the requests of all the probes at once, giving the aggregated result
+/// along with the result of every probe.
+async fn probe_all<B: Behaviour>(
+    requests: Vec<(RequestBuilder, Mode, u32)>,
+    aggregation: Aggregation,
+) -> (bool, Vec<Result<(), Error<<B::Answer as Answer>::Fail>>>) {
+    let weights: Vec<_> = requests.iter().map(|(_, _, weight)| *weight).collect();
+    let results =
+        future::join_all(requests.into_iter().map(|(request, mode, _)| {
+            ping_once::<B::Question, B::Answer, B::Codec>(request, mode)
+        }))
+        .await;
+    let passed = aggregation.passed(
+        weights
+            .into_iter()
+            .zip(&results)
+            .map(|(weight, result)| (weight, result.is_ok())),
+    );
+    (passed, results)
+}
+
+/// Result of the single ping made on demand, see [`Host::check_now`](crate::host::Host::check_now).
+#[derive(Debug)]
+pub struct CheckReport<R: Display> {
+    pub health: Health,
+    /// Time all the probes took.
+    pub latency: Duration,
+    /// Errors of the failed probes by their paths (some may fail even if the host is up).
+    pub failures: Vec<(String, Error<R>)>,
+}
+
+/// Pings once with the given probes, without any background loop.
+pub async fn check<B: Behaviour>(
+    probes: Vec<Probe>,
+    aggregation: Aggregation,
+) -> CheckReport<<B::Answer as Answer>::Fail> {
+    let started = Instant::now();
+    let xri = format!("{}-check", ping_xri_base());
+    let (requests, paths): (Vec<_>, Vec<_>) = probes
+        .into_iter()
+        .map(|probe| {
+            let request = probe.request.header(XRI_HEADER, &xri);
+            ((request, probe.mode, probe.weight), probe.path)
+        })
+        .unzip();
+    let (passed, results) = probe_all::<B>(requests, aggregation).await;
+    CheckReport {
+        health: if passed { Health::Up } else { Health::Down },
+        latency: started.elapsed(),
+        failures: paths
+            .into_iter()
+            .zip(results)
+            .filter_map(|(path, result)| Some((path, result.err()?)))
+            .collect(),
+    }
+}
+
+/// Spawns repeated sending of the request, ignoring its results.
+pub fn keeper<B: Behaviour>(
+    request: RequestBuilder,
+    period: Duration,
+) -> <<B as Behaviour>::Handling as Handling>::Handle {
+    let finish = Finish::default();
+    let finish_clone = finish.clone();
+    let keeper = async move {
+        while !finish.is_requested() {
+            if let Some(request) = request.try_clone() {
+                if let Ok(response) = request.send().await {
+                    // NOTE: connection returns to the pool only after the body is received
+                    let _ = response.bytes().await;
+                }
+            }
+            sleep_unless_finished::<B::Sleep>(period, &finish).await;
+        }
+    };
+    B::Handling::spawn(keeper, finish_clone)
+}
+
+async fn sleep_unless_finished<S: Sleep>(duration: Duration, finish: &Finish) {
+    let sleep = S::sleep(duration);
+    let finished = finish.requested();
+    futures::pin_mut!(finished);
+    future::select(sleep, finished).await;
+}
+
+/// Ping request IDs are made of this prefix and the attempt number, so the pingers
+/// of different hosts and processes rarely collide.
+fn ping_xri_base() -> String {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("ping-{:x}", started.as_millis())
+}
+
+/// Single failed probe is described by its error, several ones are prefixed with their paths.
+fn describe_failures<R: Display>(failures: &[(&Probe, Error<R>)], probes: usize) -> String {
+    match failures {
+        [] => "Aggregated ping result is negative".into(),
+        [(_, error)] if probes == 1 => error.to_string(),
+        failures => failures
+            .iter()
+            .map(|(probe, error)| format!("'{}': {}", probe.path, error))
+            .collect::<Vec<_>>()
+            .join("; "),
+    }
+}
+
+/// Pause till the next ping to keep the period between their starts,
+/// or `None` if the ping overran the period.
+fn pause_after(period: Duration, elapsed: Duration) -> Option<Duration> {
+    period.checked_sub(elapsed)
+}
+
+/// Period after one more failed ping: linearly growing, but not over the maximum.
+fn grown_period(current: Duration, period: Duration, max_period: Duration) -> Duration {
+    (current + period).min(max_period)
+}
+
+/// Gives the previous health if it differs from the new one.
+fn change_health(health: &mut Health, new_health: Health) -> Option<Health> {
+    (*health != new_health).then(|| std::mem::replace(health, new_health))
+}
+
+#[derive(Debug, thiserror::Error)] // NOTE: impossible to derive from Clone because reqwest::Error doesn't implement it
+pub enum Error<R: Display> {
+    #[error("Failed sending ping request: {0}")]
+    Request(reqwest::Error),
+    #[error("Failed receiving ping response: {0}")]
+    Response(reqwest::Error),
+    #[error("Failed encoding ping request: {0}")]
+    Encode(CodecError),
+    #[error("Failed decoding ping response: {0}")]
+    Decode(CodecError),
+    #[error("Negative ping result with status {status}: {result}")]
+    NegativeResult { status: StatusCode, result: R },
+    #[error("Negative ping status {0}")]
+    NegativeStatus(StatusCode),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use reqwest::Client;
+
+    use super::*;
+
+    /// Answers the single request with the given raw response, giving the URL to send it to.
+    fn serve_once(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Test listener should bind");
+        let address = listener
+            .local_addr()
+            .expect("Test listener should have address");
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Test connection should arrive");
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer);
+            let _ = stream.write_all(response.as_bytes());
+        });
+        format!("http://{}/health", address)
+    }
+
+    #[derive(Deserialize)]
+    struct VersionedAnswer {}
+
+    impl Answer for VersionedAnswer {
+        type Fail = String;
+
+        fn positivness(self) -> Result<(), Self::Fail> {
+            Ok(())
+        }
+
+        fn evaluate(self, info: &ResponseInfo) -> Result<(), Self::Fail> {
+            match info.headers.get("x-version") {
+                Some(version) if version == "2" => Ok(()),
+                other => Err(format!("unexpected version {:?}", other)),
+            }
+        }
+    }
+
+    async fn ping_versioned(response: &'static str) -> Result<(), Error<String>> {
+        let request = Client::new().get(serve_once(response));
+        ping_once::<EmptyQuestion, VersionedAnswer, Json>(request, Mode::Answer).await
+    }
+
+    #[tokio::test]
+    async fn answer_evaluates_headers() {
+        ping_versioned(
+            "HTTP/1.1 200 OK\r\nX-Version: 2\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        )
+        .await
+        .expect("Ping with the expected version should succeed");
+
+        let error = ping_versioned(
+            "HTTP/1.1 200 OK\r\nX-Version: 1\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        )
+        .await
+        .expect_err("Ping with other version should fail");
+
+        assert!(matches!(error, Error::NegativeResult { .. }));
+    }
+
+    #[tokio::test]
+    async fn text_answer_is_accepted() {
+        let request = Client::new().get(serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK",
+        ));
+        ping_once::<EmptyQuestion, TextAnswer, Json>(request, Mode::Answer)
+            .await
+            .expect("Plain text ping response should be accepted");
+    }
+
+    #[tokio::test]
+    async fn empty_answer_ignores_body() {
+        for response in [
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 7\r\nConnection: close\r\n\r\nhealthy",
+        ] {
+            let request = Client::new().get(serve_once(response));
+            ping_once::<EmptyQuestion, EmptyAnswer, Json>(request, Mode::Answer)
+                .await
+                .expect("Any successful ping response should be accepted");
+        }
+    }
+
+    #[tokio::test]
+    async fn status_only_mode_skips_answer() {
+        let request = Client::new().head(serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ));
+        ping_once::<EmptyQuestion, VersionedAnswer, Json>(request, Mode::Head)
+            .await
+            .expect("Successful status should be enough");
+
+        let request = Client::new().get(serve_once(
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ));
+        let error = ping_once::<EmptyQuestion, VersionedAnswer, Json>(request, Mode::Range)
+            .await
+            .expect_err("Unsuccessful status should fail");
+
+        assert!(matches!(
+            error,
+            Error::NegativeStatus(StatusCode::SERVICE_UNAVAILABLE)
+        ));
+    }
+
+    #[test]
+    fn period_growth_is_bounded() {
+        let (period, max_period) = (Duration::from_secs(4), Duration::from_secs(10));
+        let periods: Vec<_> = std::iter::successors(Some(period), |current| {
+            Some(grown_period(*current, period, max_period))
+        })
+        .take(5)
+        .map(|period| period.as_secs())
+        .collect();
+
+        assert_eq!(periods, [4, 8, 10, 10, 10]);
+    }
+
+    #[test]
+    fn pause_keeps_period_between_pings() {
+        let period = Duration::from_secs(4);
+
+        assert_eq!(
+            pause_after(period, Duration::from_secs(1)),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(pause_after(period, Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn probes_are_aggregated() {
+        let config: Config = toml::from_str(
+            r#"
+                path = "/live"
+                weight = 3
+                probes = [{ path = "/ready", mode = "head" }]
+                aggregation = { weighted = 0.7 }
+            "#,
+        )
+        .expect("Config should deserialize smoothly");
+
+        assert_eq!(config.probes[0].path, "/ready");
+        assert_eq!(config.probes[0].mode, Mode::Head);
+        assert_eq!(config.probes[0].weight, 1);
+        assert_eq!(config.aggregation, Aggregation::Weighted(0.7));
+
+        let live_only = [(3, true), (1, false)];
+        let ready_only = [(3, false), (1, true)];
+
+        assert!(!Aggregation::All.passed(live_only));
+        assert!(Aggregation::Any.passed(ready_only));
+        assert!(config.aggregation.passed(live_only));
+        assert!(!config.aggregation.passed(ready_only));
+    }
+}
@@ -0,0 +1,128 @@
+use std::{future::Future, time::Duration};
+
+use async_compat::Compat;
+
+use super::{AbortableHandle, Finish, Handle, Handling, Sleep};
+
+/// Sleeping with the smol timer.
+pub struct SmolSleep;
+
+#[async_trait::async_trait]
+impl Sleep for SmolSleep {
+    async fn sleep(duration: Duration) {
+        smol::Timer::after(duration).await;
+    }
+}
+
+/// Spawning the pinger as a smol task. Requests are driven by the tokio runtime
+/// provided with async-compat, since reqwest can't work without it.
+pub struct SmolHandling;
+
+impl Handling for SmolHandling {
+    type Handle = AbortableHandle;
+
+    fn spawn<Fut>(f: Fut, finish: Finish) -> Self::Handle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (f, handle) = AbortableHandle::wrap(f, finish);
+        smol::spawn(Compat::new(f)).detach();
+        handle
+    }
+
+    fn stop(handle: Self::Handle) {
+        handle.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future::{self, Either};
+    use reqwest::Client;
+
+    use super::*;
+    use crate::{
+        host::Json,
+        ping::{pinger, Behaviour, DontProcessError, EmptyAnswer, EmptyQuestion, Probe, Settings},
+    };
+
+    struct SmolBehaviour;
+
+    impl Behaviour for SmolBehaviour {
+        type Question = EmptyQuestion;
+        type Answer = EmptyAnswer;
+        type Codec = Json;
+        type Sleep = SmolSleep;
+        type ProcessError = DontProcessError<std::convert::Infallible>;
+        type Handling = SmolHandling;
+    }
+
+    fn spawn_pinger() -> AbortableHandle {
+        let probes = vec![Probe {
+            request: Client::new().get("http://127.0.0.1:9/health"),
+            mode: Default::default(),
+            path: "/health".into(),
+            weight: 1,
+        }];
+        let settings = Settings {
+            period: Duration::from_secs(3600),
+            max_period: Duration::from_secs(3600),
+            aggregation: Default::default(),
+            address: "127.0.0.1:9".into(),
+        };
+        #[cfg(feature = "callbacks")]
+        return pinger::<SmolBehaviour, crate::host::TrivialCallbacks>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            futures::channel::oneshot::channel().0,
+            #[cfg(feature = "events")]
+            None,
+        );
+        #[cfg(not(feature = "callbacks"))]
+        return pinger::<SmolBehaviour>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            futures::channel::oneshot::channel().0,
+            #[cfg(feature = "events")]
+            None,
+        );
+    }
+
+    async fn stops_within(handle: &AbortableHandle, limit: Duration) -> bool {
+        let stopped = handle.stopped();
+        let timer = SmolSleep::sleep(limit);
+        futures::pin_mut!(stopped, timer);
+        matches!(future::select(stopped, timer).await, Either::Left(_))
+    }
+
+    #[test]
+    fn finish_interrupts_sleep() {
+        smol::block_on(async {
+            let handle = spawn_pinger();
+            SmolSleep::sleep(Duration::from_millis(100)).await;
+            handle.finish();
+            assert!(
+                stops_within(&handle, Duration::from_secs(5)).await,
+                "Pinger should finish without waiting for the whole period"
+            );
+        })
+    }
+
+    #[test]
+    fn abort_stops_at_once() {
+        smol::block_on(async {
+            let handle = spawn_pinger();
+            handle.abort();
+            assert!(
+                stops_within(&handle, Duration::from_secs(5)).await,
+                "Pinger should be aborted at once"
+            );
+        })
+    }
+}
@@ -0,0 +1,273 @@
+use std::{future::Future, time::Duration};
+
+use tokio::{sync::watch, task::JoinHandle};
+
+use super::{Finish, Handle, Handling, Sleep};
+
+/// Sleeping with the tokio timer.
+pub struct TokioSleep;
+
+#[async_trait::async_trait]
+impl Sleep for TokioSleep {
+    async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// Spawning the pinger as a tokio task. Requires to be used within tokio runtime.
+pub struct TokioHandling;
+
+impl Handling for TokioHandling {
+    type Handle = TokioHandle;
+
+    fn spawn<Fut>(f: Fut, finish: Finish) -> Self::Handle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (done_sender, done) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            f.await;
+            let _ = done_sender.send(true);
+        });
+        TokioHandle { task, finish, done }
+    }
+
+    fn stop(handle: Self::Handle) {
+        handle.finish()
+    }
+}
+
+pub struct TokioHandle {
+    task: JoinHandle<()>,
+    finish: Finish,
+    done: watch::Receiver<bool>,
+}
+
+#[async_trait::async_trait]
+impl Handle for TokioHandle {
+    fn abort(&self) {
+        self.task.abort()
+    }
+
+    fn finish(&self) {
+        self.finish.request()
+    }
+
+    async fn stopped(&self) {
+        let mut done = self.done.clone();
+        // NOTE: sender is dropped without sending if the task is aborted, which means stop as well
+        while !*done.borrow_and_update() {
+            if done.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use futures::channel::oneshot;
+    use reqwest::Client;
+
+    use super::*;
+    use crate::{
+        host::Json,
+        ping::{
+            pinger, Behaviour, Context, DontProcessError, EmptyAnswer, EmptyQuestion, Error,
+            Health, Probe, ProcessError, Settings,
+        },
+    };
+
+    struct TokioBehaviour;
+
+    impl Behaviour for TokioBehaviour {
+        type Question = EmptyQuestion;
+        type Answer = EmptyAnswer;
+        type Codec = Json;
+        type Sleep = TokioSleep;
+        type ProcessError = DontProcessError<std::convert::Infallible>;
+        type Handling = TokioHandling;
+    }
+
+    #[derive(Clone, Default)]
+    struct XriCollector(Arc<Mutex<Vec<String>>>);
+
+    impl ProcessError<Infallible> for XriCollector {
+        fn process_ping_error(&self, context: &Context, _error: Error<Infallible>) {
+            self.0.lock().unwrap().push(context.xri.into());
+        }
+
+        fn process_request_clone_fail(&self, _context: &Context) {}
+    }
+
+    struct CollectingBehaviour;
+
+    impl Behaviour for CollectingBehaviour {
+        type Question = EmptyQuestion;
+        type Answer = EmptyAnswer;
+        type Codec = Json;
+        type Sleep = TokioSleep;
+        type ProcessError = XriCollector;
+        type Handling = TokioHandling;
+    }
+
+    fn spawn_pinger() -> TokioHandle {
+        spawn_pinger_with::<TokioBehaviour>(Default::default(), oneshot::channel().0)
+    }
+
+    fn spawn_pinger_with<B: Behaviour<Handling = TokioHandling>>(
+        process_error: B::ProcessError,
+        first_result: oneshot::Sender<Health>,
+    ) -> TokioHandle {
+        let probes = vec![Probe {
+            request: Client::new().get("http://127.0.0.1:9/health"),
+            mode: Default::default(),
+            path: "/health".into(),
+            weight: 1,
+        }];
+        let settings = Settings {
+            period: Duration::from_secs(3600),
+            max_period: Duration::from_secs(3600),
+            aggregation: Default::default(),
+            address: "127.0.0.1:9".into(),
+        };
+        #[cfg(feature = "callbacks")]
+        return pinger::<B, crate::host::TrivialCallbacks>(
+            probes,
+            settings,
+            process_error,
+            Default::default(),
+            first_result,
+            #[cfg(feature = "events")]
+            None,
+        );
+        #[cfg(not(feature = "callbacks"))]
+        return pinger::<B>(
+            probes,
+            settings,
+            process_error,
+            Default::default(),
+            first_result,
+            #[cfg(feature = "events")]
+            None,
+        );
+    }
+
+    #[tokio::test]
+    async fn finish_interrupts_sleep() {
+        let handle = spawn_pinger();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        handle.finish();
+        tokio::time::timeout(Duration::from_secs(5), handle.stopped())
+            .await
+            .expect("Pinger should finish without waiting for the whole period");
+    }
+
+    #[tokio::test]
+    async fn abort_stops_at_once() {
+        let handle = spawn_pinger();
+        handle.abort();
+        tokio::time::timeout(Duration::from_secs(5), handle.stopped())
+            .await
+            .expect("Pinger should be aborted at once");
+    }
+
+    #[tokio::test]
+    async fn ping_errors_carry_xri() {
+        let collector = XriCollector::default();
+        let handle =
+            spawn_pinger_with::<CollectingBehaviour>(collector.clone(), oneshot::channel().0);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while collector.0.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Ping to the closed port should fail");
+        handle.abort();
+
+        let xris = collector.0.lock().unwrap();
+
+        assert!(xris[0].starts_with("ping-"));
+        assert!(xris[0].ends_with("-1"));
+    }
+
+    #[tokio::test]
+    async fn first_result_is_sent_at_once() {
+        let (first_result, receiver) = oneshot::channel();
+        let handle = spawn_pinger_with::<TokioBehaviour>(Default::default(), first_result);
+        let health = tokio::time::timeout(Duration::from_secs(5), receiver)
+            .await
+            .expect("First ping should not wait for the period")
+            .expect("First result should be sent");
+        handle.abort();
+
+        assert_eq!(health, Health::Down);
+    }
+
+    #[cfg(feature = "events")]
+    #[tokio::test]
+    async fn overran_ping_is_reported() {
+        use crate::host::HostEvent;
+
+        // NOTE: connections are accepted by the system, but never answered
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Listener should bind");
+        let address = listener
+            .local_addr()
+            .expect("Listener should have address")
+            .to_string();
+        let probes = vec![Probe {
+            request: Client::new()
+                .get(format!("http://{}/health", address))
+                .timeout(Duration::from_millis(100)),
+            mode: Default::default(),
+            path: "/health".into(),
+            weight: 1,
+        }];
+        let settings = Settings {
+            period: Duration::from_millis(20),
+            max_period: Duration::from_millis(20),
+            aggregation: Default::default(),
+            address,
+        };
+        let (events, received) = std::sync::mpsc::channel();
+        #[cfg(feature = "callbacks")]
+        let handle = pinger::<TokioBehaviour, crate::host::TrivialCallbacks>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            oneshot::channel().0,
+            Some(events),
+        );
+        #[cfg(not(feature = "callbacks"))]
+        let handle = pinger::<TokioBehaviour>(
+            probes,
+            settings,
+            Default::default(),
+            Default::default(),
+            oneshot::channel().0,
+            Some(events),
+        );
+        let overran = tokio::task::spawn_blocking(move || loop {
+            match received.recv_timeout(Duration::from_secs(5)) {
+                Ok(HostEvent::PingOverran { elapsed, period }) => break Some((elapsed, period)),
+                Ok(_) => continue,
+                Err(_) => break None,
+            }
+        })
+        .await
+        .expect("Events should be received");
+        handle.abort();
+
+        let (elapsed, period) = overran.expect("Overran ping should be reported");
+
+        assert!(elapsed > period);
+    }
+}
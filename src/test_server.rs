@@ -0,0 +1,371 @@
+//! Tiny embedded HTTP/1 server with programmable routes, latency and failure injection,
+//! to test the clients built on the crate end-to-end without real upstreams. Expectations
+//! on the requests received can be set with [`TestServer::expect`] and checked with [`TestServer::verify`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures::channel::oneshot;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Server,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::Serialize;
+
+use crate::address::Address;
+
+/// Answer of the server to the matching request.
+#[derive(Clone, Debug)]
+pub struct Reply {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// Time to wait before answering (or failing).
+    pub delay: Duration,
+    /// Whether the connection is dropped instead of answering.
+    pub abort: bool,
+}
+
+impl Reply {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            delay: Duration::ZERO,
+            abort: false,
+        }
+    }
+
+    /// Empty 200 OK reply.
+    pub fn ok() -> Self {
+        Self::new(StatusCode::OK)
+    }
+
+    /// Reply dropping the connection without any response, like a crashed upstream.
+    pub fn abort() -> Self {
+        Self {
+            abort: true,
+            ..Self::new(StatusCode::BAD_GATEWAY)
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn body<B: Into<Bytes>>(mut self, body: B) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sets the body to the JSON of the value (panics if it can't be serialized).
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        let body = serde_json::to_vec(value).expect("Test server reply should be serializable");
+        self.header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body)
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Request received by the server.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    fn has_header(&self, name: &HeaderName, value: Option<&HeaderValue>) -> bool {
+        let mut values = self.headers.get_all(name).iter();
+        match value {
+            Some(value) => values.any(|x| x == value),
+            None => values.next().is_some(),
+        }
+    }
+}
+
+impl Display for RecordedRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} {}", self.method, self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Requests expected to be received, see [`TestServer::expect`].
+#[derive(Clone, Debug)]
+struct Expectation {
+    method: Method,
+    path: String,
+    /// Exact number of matching requests, or None for at least one.
+    times: Option<usize>,
+    headers: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl Expectation {
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        request.method == self.method
+            && request.path == self.path
+            && self
+                .headers
+                .iter()
+                .all(|(name, value)| request.has_header(name, value.as_ref()))
+    }
+
+    fn is_met(&self, received: usize) -> bool {
+        match self.times {
+            Some(times) => received == times,
+            None => received > 0,
+        }
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} {}", self.method, self.path)?;
+        for (index, (name, value)) in self.headers.iter().enumerate() {
+            f.write_str(if index == 0 { " with " } else { ", " })?;
+            match value {
+                Some(value) => write!(f, "{}: {:?}", name, value)?,
+                None => write!(f, "{}", name)?,
+            }
+        }
+        match self.times {
+            Some(times) => write!(f, " {} time(s)", times),
+            None => f.write_str(" at least once"),
+        }
+    }
+}
+
+/// Handle to refine the expectation just set.
+pub struct Expect<'a> {
+    server: &'a TestServer,
+    index: usize,
+}
+
+impl Expect<'_> {
+    /// Expects exactly that many matching requests instead of at least one.
+    pub fn times(self, times: usize) -> Self {
+        self.update(|expectation| expectation.times = Some(times))
+    }
+
+    /// Expects the requests to have the header with any value (panics if the name is invalid).
+    pub fn with_header(self, name: &str) -> Self {
+        let name = header_name(name);
+        self.update(|expectation| expectation.headers.push((name, None)))
+    }
+
+    /// Expects the requests to have the header with the value (panics if the name is invalid).
+    pub fn with_header_value(self, name: &str, value: HeaderValue) -> Self {
+        let name = header_name(name);
+        self.update(|expectation| expectation.headers.push((name, Some(value))))
+    }
+
+    fn update<F: FnOnce(&mut Expectation)>(self, change: F) -> Self {
+        change(&mut self.server.lock().expectations[self.index]);
+        self
+    }
+}
+
+fn header_name(name: &str) -> HeaderName {
+    HeaderName::from_bytes(name.as_bytes()).expect("Expected header name should be valid")
+}
+
+#[derive(Default)]
+struct State {
+    routes: HashMap<(Method, String), VecDeque<Reply>>,
+    requests: Vec<RecordedRequest>,
+    expectations: Vec<Expectation>,
+}
+
+type Shared = Arc<Mutex<State>>;
+
+/// Server listening on a random local port until dropped. Requests not matching any route
+/// get 404 Not Found.
+pub struct TestServer {
+    address: Address,
+    state: Shared,
+    _stop: oneshot::Sender<()>,
+}
+
+impl TestServer {
+    /// Starts the server on the current Tokio runtime.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+        let state = Shared::default();
+        let service_state = state.clone();
+        let server = Server::from_tcp(listener)
+            .map_err(io::Error::other)?
+            .http1_only(true)
+            .serve(make_service_fn(move |_| {
+                let state = service_state.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| answer(state.clone(), request)))
+                }
+            }));
+        let (stop, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = stopped.await;
+        }));
+        let address = Address::new("127.0.0.1", port)
+            .map_err(|source| io::Error::new(io::ErrorKind::InvalidInput, source))?;
+        Ok(Self {
+            address,
+            state,
+            _stop: stop,
+        })
+    }
+
+    /// Address to be used as the host target.
+    pub fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    /// Makes the server answer requests with the given method and path (without query)
+    /// with the reply, replacing any replies set before.
+    pub fn route(&self, method: Method, path: &str, reply: Reply) {
+        self.route_sequence(method, path, vec![reply]);
+    }
+
+    /// Same as [`Self::route`], but the replies are used one by one, and the last one
+    /// is repeated then (e.g. two failures and then success). Does nothing if there are no replies.
+    pub fn route_sequence<I>(&self, method: Method, path: &str, replies: I)
+    where
+        I: IntoIterator<Item = Reply>,
+    {
+        let replies: VecDeque<_> = replies.into_iter().collect();
+        if replies.is_empty() {
+            return;
+        }
+        self.lock()
+            .routes
+            .insert((method, path.to_owned()), replies);
+    }
+
+    /// Expects the requests with the given method and path (without query) to be received
+    /// by the time of [`Self::verify`], at least once unless refined with [`Expect::times`].
+    pub fn expect(&self, method: Method, path: &str) -> Expect<'_> {
+        let mut state = self.lock();
+        state.expectations.push(Expectation {
+            method,
+            path: path.to_owned(),
+            times: None,
+            headers: Vec::new(),
+        });
+        Expect {
+            server: self,
+            index: state.expectations.len() - 1,
+        }
+    }
+
+    /// Descriptions of the expectations not met by the requests received so far.
+    pub fn unmet(&self) -> Vec<String> {
+        let state = self.lock();
+        state
+            .expectations
+            .iter()
+            .filter_map(|expectation| {
+                let received = state
+                    .requests
+                    .iter()
+                    .filter(|request| expectation.matches(request))
+                    .count();
+                (!expectation.is_met(received))
+                    .then(|| format!("expected {}, received {} time(s)", expectation, received))
+            })
+            .collect()
+    }
+
+    /// Panics (failing the test) if any expectation is not met, listing them along with
+    /// all the requests received.
+    pub fn verify(&self) {
+        let unmet = self.unmet();
+        if unmet.is_empty() {
+            return;
+        }
+        let mut message = String::from("Test server expectations are not met:\n");
+        for line in unmet {
+            message.push_str(&format!("  - {}\n", line));
+        }
+        message.push_str("Received requests:\n");
+        for request in self.requests() {
+            message.push_str(&format!("  + {}\n", request));
+        }
+        panic!("{}", message);
+    }
+
+    /// All the requests received so far, in the order of arrival.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+async fn answer(
+    state: Shared,
+    request: hyper::Request<Body>,
+) -> Result<hyper::Response<Body>, Box<dyn StdError + Send + Sync>> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    let path = parts.uri.path().to_owned();
+    let reply = {
+        let mut state = state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.requests.push(RecordedRequest {
+            method: parts.method.clone(),
+            path: path.clone(),
+            query: parts.uri.query().map(ToOwned::to_owned),
+            headers: parts.headers,
+            body,
+        });
+        match state.routes.get_mut(&(parts.method, path)) {
+            Some(replies) if replies.len() > 1 => replies.pop_front(),
+            Some(replies) => replies.front().cloned(),
+            None => None,
+        }
+    }
+    .unwrap_or_else(|| Reply::new(StatusCode::NOT_FOUND));
+    if !reply.delay.is_zero() {
+        tokio::time::sleep(reply.delay).await;
+    }
+    if reply.abort {
+        // NOTE: the failed service makes the server drop the connection without a response
+        return Err("Test server reply is aborted".into());
+    }
+    let mut response = hyper::Response::new(Body::from(reply.body));
+    *response.status_mut() = reply.status;
+    *response.headers_mut() = reply.headers;
+    Ok(response)
+}
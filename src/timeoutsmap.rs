@@ -1,7 +1,8 @@
-use humantime_serde::Serde;
+use humantime_serde::re::humantime::format_duration;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     hash::Hash,
     ops::{Index, IndexMut},
     time::Duration,
@@ -10,14 +11,79 @@ use std::{
 #[derive(PartialEq, Eq, Hash, Default, Deserialize)]
 pub struct TrivialKey;
 
-pub trait Array: IndexMut<usize, Output = Duration> {
-    fn new(default: Duration) -> Self;
+/// Settings applied to a request class: its timeout along with optional retry, backoff and hedging ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Policy {
+    pub timeout: Duration,
+    /// How many times a retryable failure may be repeated.
+    pub retries: u32,
+    /// Delay between the repeated attempts.
+    pub backoff: Duration,
+    /// Delay after which a concurrent (hedged) attempt may be started, if any.
+    pub hedge: Option<Duration>,
 }
 
-pub type UsualArray<const N: usize> = [Duration; N];
+impl Policy {
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    /// The shortest config entry turning `base` into this policy
+    /// (the timeout-only form is used if nothing else differs).
+    fn entry_from(&self, base: &Self) -> PolicyEntry {
+        let same_but_timeout = Self {
+            timeout: base.timeout,
+            ..*self
+        };
+        if same_but_timeout == *base {
+            return PolicyEntry::Timeout(self.timeout);
+        }
+        PolicyEntry::Full(PolicyOverride {
+            timeout: differs(self.timeout, base.timeout),
+            retries: differs(self.retries, base.retries),
+            backoff: differs(self.backoff, base.backoff),
+            hedge: self.hedge.filter(|_| self.hedge != base.hedge),
+        })
+    }
+}
+
+fn differs<T: PartialEq>(this: T, that: T) -> Option<T> {
+    if this != that {
+        Some(this)
+    } else {
+        None
+    }
+}
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "timeout {}", format_duration(self.timeout))?;
+        if self.retries > 0 {
+            write!(
+                f,
+                ", {} retries with {} backoff",
+                self.retries,
+                format_duration(self.backoff)
+            )?;
+        }
+        if let Some(hedge) = self.hedge {
+            write!(f, ", hedge after {}", format_duration(hedge))?;
+        }
+        Ok(())
+    }
+}
+
+pub trait Array: IndexMut<usize, Output = Policy> {
+    fn new(default: Policy) -> Self;
+}
+
+pub type UsualArray<const N: usize> = [Policy; N];
 
 impl<const N: usize> Array for UsualArray<N> {
-    fn new(default: Duration) -> Self {
+    fn new(default: Policy) -> Self {
         [default; N]
     }
 }
@@ -29,6 +95,9 @@ pub trait Params {
     type Array: Array;
 
     fn key_as_usize(key: &Self::Key) -> usize;
+
+    /// All the keys, needed to turn the map back into config.
+    fn keys() -> Vec<Self::Key>;
 }
 
 pub struct TrivialParams;
@@ -40,24 +109,80 @@ impl Params for TrivialParams {
     fn key_as_usize(_: &Self::Key) -> usize {
         0
     }
+
+    fn keys() -> Vec<Self::Key> {
+        vec![TrivialKey]
+    }
+}
+
+/// Config value for a single key: either just a timeout (like `"200ms"`)
+/// or a table with any of `timeout`, `retries`, `backoff` and `hedge`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PolicyEntry {
+    Timeout(#[serde(with = "humantime_serde")] Duration),
+    Full(PolicyOverride),
+}
+
+/// Policy settings of a key, absent ones are taken from the default policy.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyOverride {
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+    #[serde(default, with = "humantime_serde")]
+    pub backoff: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub hedge: Option<Duration>,
+}
+
+impl PolicyEntry {
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Timeout(timeout) => Some(*timeout),
+            Self::Full(policy) => policy.timeout,
+        }
+    }
+
+    fn apply(self, base: Policy) -> Policy {
+        match self {
+            Self::Timeout(timeout) => Policy { timeout, ..base },
+            Self::Full(policy) => Policy {
+                timeout: policy.timeout.unwrap_or(base.timeout),
+                retries: policy.retries.unwrap_or(base.retries),
+                backoff: policy.backoff.unwrap_or(base.backoff),
+                hedge: policy.hedge.or(base.hedge),
+            },
+        }
+    }
+}
+
+impl From<Duration> for PolicyEntry {
+    fn from(src: Duration) -> Self {
+        Self::Timeout(src)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct TimeoutsMapConfig<K: Eq + Hash + Default = TrivialKey> {
+pub struct PolicyMapConfig<K: Eq + Hash + Default = TrivialKey> {
     #[serde(
         with = "humantime_serde",
         default = "default_timeouts_map_config_default"
     )]
     pub default: Duration,
     #[serde(flatten)]
-    pub map: HashMap<K, Serde<Duration>>,
+    pub map: HashMap<K, PolicyEntry>,
 }
 
+/// The policy map config was a timeouts-only one before, so the old name is kept.
+pub type TimeoutsMapConfig<K = TrivialKey> = PolicyMapConfig<K>;
+
 fn default_timeouts_map_config_default() -> Duration {
     Duration::from_millis(120)
 }
 
-impl<K: Eq + Hash + Default> TimeoutsMapConfig<K> {
+impl<K: Eq + Hash + Default> PolicyMapConfig<K> {
     pub fn only_default(default_ms: u64) -> Self {
         Self {
             default: Duration::from_millis(default_ms),
@@ -70,7 +195,7 @@ impl<K: Eq + Hash + Default> TimeoutsMapConfig<K> {
     }
 }
 
-impl<K: Eq + Hash + Default> Default for TimeoutsMapConfig<K> {
+impl<K: Eq + Hash + Default> Default for PolicyMapConfig<K> {
     fn default() -> Self {
         Self {
             default: Self::def_default(),
@@ -80,22 +205,109 @@ impl<K: Eq + Hash + Default> Default for TimeoutsMapConfig<K> {
 }
 
 #[derive(Clone, Debug)]
-pub struct TimeoutsMap<P: Params = TrivialParams>(P::Array);
+pub struct PolicyMap<P: Params = TrivialParams>(P::Array, Policy);
+
+/// The policy map was a timeouts-only one before, so the old name is kept.
+pub type TimeoutsMap<P = TrivialParams> = PolicyMap<P>;
+
+impl<P: Params> PolicyMap<P> {
+    pub fn policy(&self, spec: &P::Key) -> &Policy {
+        &self.0[P::key_as_usize(spec)]
+    }
+
+    /// Same as [`PolicyMap::policy`], but falls back to the default key if none given.
+    pub fn get(&self, spec: Option<&P::Key>) -> &Policy {
+        match spec {
+            Some(spec) => self.policy(spec),
+            None => self.policy(&P::Key::default()),
+        }
+    }
+
+    /// Config producing the same effective policies, listing only the keys differing from the default.
+    pub fn to_config(&self) -> PolicyMapConfig<P::Key> {
+        let map = P::keys()
+            .into_iter()
+            .filter_map(|spec| {
+                let policy = self.0[P::key_as_usize(&spec)];
+                (policy != self.1).then(|| (spec, policy.entry_from(&self.1)))
+            })
+            .collect();
+        PolicyMapConfig {
+            default: self.1.timeout,
+            map,
+        }
+    }
+
+    /// Reports every key's effective policy, whether it is set explicitly in the given config
+    /// and whether it differs from what the config would produce.
+    pub fn diff(&self, config: &PolicyMapConfig<P::Key>) -> PolicyDiff<P::Key> {
+        let default = Policy::with_timeout(config.default);
+        let entries = P::keys()
+            .into_iter()
+            .map(|spec| {
+                let effective = self.0[P::key_as_usize(&spec)];
+                let entry = config.map.get(&spec);
+                let expected = entry.map_or(default, |entry| entry.clone().apply(default));
+                PolicyDiffEntry {
+                    explicit: entry.is_some(),
+                    expected: differs(expected, effective),
+                    effective,
+                    spec,
+                }
+            })
+            .collect();
+        PolicyDiff(entries)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyDiffEntry<K> {
+    pub spec: K,
+    pub effective: Policy,
+    /// Whether the config sets the key explicitly (otherwise the default is used).
+    pub explicit: bool,
+    /// Policy the config would produce, if it differs from the effective one.
+    pub expected: Option<Policy>,
+}
 
-impl<P: Params> From<TimeoutsMapConfig<P::Key>> for TimeoutsMap<P> {
-    fn from(TimeoutsMapConfig { default, map }: TimeoutsMapConfig<P::Key>) -> Self {
-        let mut this = Self(P::Array::new(default));
-        map.into_iter()
-            .for_each(|(spec, duration)| this.0[P::key_as_usize(&spec)] = duration.into_inner());
+/// Per-key report of [`PolicyMap::diff`], displayed as one line per key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyDiff<K>(pub Vec<PolicyDiffEntry<K>>);
+
+impl<K: Debug> Display for PolicyDiff<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for entry in &self.0 {
+            let source = if entry.explicit {
+                "explicit"
+            } else {
+                "default"
+            };
+            write!(f, "{:?}: {} ({})", entry.spec, entry.effective, source)?;
+            if let Some(expected) = &entry.expected {
+                write!(f, ", config has {}", expected)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<P: Params> From<PolicyMapConfig<P::Key>> for PolicyMap<P> {
+    fn from(PolicyMapConfig { default, map }: PolicyMapConfig<P::Key>) -> Self {
+        let default = Policy::with_timeout(default);
+        let mut this = Self(P::Array::new(default), default);
+        map.into_iter().for_each(|(spec, entry)| {
+            this.0[P::key_as_usize(&spec)] = entry.apply(default);
+        });
         this
     }
 }
 
-impl<P: Params> Index<P::Key> for TimeoutsMap<P> {
+impl<P: Params> Index<&P::Key> for PolicyMap<P> {
     type Output = Duration;
 
-    fn index(&self, spec: P::Key) -> &Self::Output {
-        &self.0[P::key_as_usize(&spec)]
+    fn index(&self, spec: &P::Key) -> &Self::Output {
+        &self.policy(spec).timeout
     }
 }
 
@@ -132,6 +344,10 @@ pub mod tests {
         fn key_as_usize(key: &Self::Key) -> usize {
             *key as usize
         }
+
+        fn keys() -> Vec<Self::Key> {
+            enum_iterator::all::<Spec>().collect()
+        }
     }
 
     const CONFIG_TEXT: &str = r#"
@@ -145,11 +361,96 @@ pub mod tests {
             toml::from_str(CONFIG_TEXT).expect("Config should deserialize smoothly");
         let timeouts = TimeoutsMap::<SpecParams>::from(config);
 
-        assert_eq!(timeouts[Spec::Alice], Duration::from_millis(222));
-        assert_eq!(timeouts[Spec::Charlie], Duration::from_millis(333));
+        assert_eq!(timeouts[&Spec::Alice], Duration::from_millis(222));
+        assert_eq!(timeouts[&Spec::Charlie], Duration::from_millis(333));
+
+        assert_eq!(timeouts[&Spec::Undefined], Duration::from_millis(111));
+        assert_eq!(timeouts[&Spec::Bob], Duration::from_millis(111));
+        assert_eq!(timeouts[&Spec::Duncan], Duration::from_millis(111));
+    }
+
+    #[test]
+    fn policies_read_and_apply() {
+        let config: PolicyMapConfig<Spec> = toml::from_str(
+            r#"
+            default = "111ms"
+            alice = "222ms"
+            bob = { retries = 2, backoff = "50ms" }
+            charlie = { timeout = "333ms", hedge = "20ms" }"#,
+        )
+        .expect("Config should deserialize smoothly");
+        let policies = PolicyMap::<SpecParams>::from(config);
+
+        assert_eq!(
+            *policies.policy(&Spec::Alice),
+            Policy::with_timeout(Duration::from_millis(222))
+        );
+        assert_eq!(
+            *policies.policy(&Spec::Bob),
+            Policy {
+                timeout: Duration::from_millis(111),
+                retries: 2,
+                backoff: Duration::from_millis(50),
+                hedge: None,
+            }
+        );
+        assert_eq!(
+            *policies.policy(&Spec::Charlie),
+            Policy {
+                timeout: Duration::from_millis(333),
+                hedge: Some(Duration::from_millis(20)),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            *policies.policy(&Spec::Duncan),
+            Policy::with_timeout(Duration::from_millis(111))
+        );
+    }
+
+    #[test]
+    fn config_roundtrip_and_diff() {
+        let config: PolicyMapConfig<Spec> = toml::from_str(
+            r#"
+            default = "111ms"
+            alice = "222ms"
+            bob = { retries = 2, backoff = "50ms" }
+            duncan = "111ms""#,
+        )
+        .expect("Config should deserialize smoothly");
+        let policies = PolicyMap::<SpecParams>::from(config.clone());
+
+        let restored = policies.to_config();
+        assert_eq!(restored.default, Duration::from_millis(111));
+        assert_eq!(restored.map.len(), 2);
+        assert_eq!(
+            restored.map[&Spec::Alice],
+            PolicyEntry::Timeout(Duration::from_millis(222))
+        );
+        assert_eq!(
+            restored.map[&Spec::Bob],
+            PolicyEntry::Full(PolicyOverride {
+                retries: Some(2),
+                backoff: Some(Duration::from_millis(50)),
+                ..Default::default()
+            })
+        );
+
+        let diff = policies.diff(&config);
+        assert!(diff.0.iter().all(|entry| entry.expected.is_none()));
+        assert_eq!(
+            diff.to_string(),
+            "Undefined: timeout 111ms (default)\n\
+             Alice: timeout 222ms (explicit)\n\
+             Bob: timeout 111ms, 2 retries with 50ms backoff (explicit)\n\
+             Charlie: timeout 111ms (default)\n\
+             Duncan: timeout 111ms (explicit)\n"
+        );
 
-        assert_eq!(timeouts[Spec::Undefined], Duration::from_millis(111));
-        assert_eq!(timeouts[Spec::Bob], Duration::from_millis(111));
-        assert_eq!(timeouts[Spec::Duncan], Duration::from_millis(111));
+        let changed = policies.diff(&PolicyMapConfig::only_default(100));
+        assert_eq!(
+            changed.0[1].expected,
+            Some(Policy::with_timeout(Duration::from_millis(100)))
+        );
     }
 }